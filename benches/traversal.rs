@@ -0,0 +1,159 @@
+//! Throughput of tracing camera rays through the scene BVH one at a time and in packets.
+//!
+//! Run with `cargo bench --bench traversal`. Packets are made either of neighbouring pixels,
+//! as when rendering 1 sample per pixel, or of samples of the same pixel, as when rendering
+//! at least `PACKET_SIZE` samples per pixel.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use nalgebra::Vector2;
+use neon::{
+    core::{
+        bvh::BvhTree,
+        camera::{Camera, CameraOverrides},
+    },
+    integrator::CAMERA_RAY_RANGE,
+    object::hittable_object::HittableObject,
+    ray::ray_packet::{RayPacket, PACKET_SIZE},
+    sampler,
+    scene::scene_generator,
+    utils::rng,
+};
+
+const WIDTH: u32 = 200;
+const LEAF_SIZE: usize = BvhTree::DEFAULT_MAX_LEAF_SIZE;
+/// Number of measured runs, the fastest one is reported
+const RUNS: u32 = 5;
+
+#[derive(Clone, Copy, Debug)]
+enum Coherence {
+    /// Packet is a block of 4x2 pixels, with a ray through the center of each
+    Pixels,
+    /// Packet is made of random samples of a single pixel
+    Samples,
+}
+
+fn main() {
+    rng::reseed(rng::DEFAULT_SEED.into());
+    let scenes = [
+        (
+            "spheres",
+            scene_generator::scene_with_spheres(24, 24, None, LEAF_SIZE),
+        ),
+        (
+            "cornell_box",
+            scene_generator::scene_with_cornell_box(None, LEAF_SIZE),
+        ),
+        (
+            "all_effects",
+            scene_generator::scene_with_all_effects(None, LEAF_SIZE),
+        ),
+        (
+            "triangles",
+            scene_generator::scene_with_triangles(None, LEAF_SIZE),
+        ),
+    ];
+
+    println!(
+        "{:<12} {:<8} {:>14} {:>14} {:>8}",
+        "scene", "packets", "scalar", "packet", "speedup"
+    );
+    for (name, mut scene) in scenes {
+        scene.override_camera(&CameraOverrides {
+            width: Some(WIDTH),
+            ..Default::default()
+        });
+        let bvh = scene.content().bvh();
+
+        for coherence in [Coherence::Pixels, Coherence::Samples] {
+            let packets = camera_ray_packets(scene.camera(), coherence);
+            let ray_count: usize = packets.iter().map(RayPacket::len).sum();
+
+            // Both paths must find the same hits, otherwise their speed doesn't matter
+            for packet in &packets {
+                let packet_hits = bvh.hit_packet(packet, &CAMERA_RAY_RANGE);
+                for (ray, packet_hit) in packet.rays().iter().zip(&packet_hits) {
+                    let hit = bvh.hit(ray, &CAMERA_RAY_RANGE);
+                    assert_eq!(
+                        hit.map(|hit| hit.t()),
+                        packet_hit.as_ref().map(|hit| hit.t()),
+                        "packet hit differs from scalar one in {name}"
+                    );
+                }
+            }
+
+            let scalar = fastest_run(|| {
+                for packet in &packets {
+                    for ray in packet.rays() {
+                        black_box(bvh.hit(ray, &CAMERA_RAY_RANGE));
+                    }
+                }
+            });
+            let packet = fastest_run(|| {
+                for packet in &packets {
+                    black_box(bvh.hit_packet(packet, &CAMERA_RAY_RANGE));
+                }
+            });
+            let rays_per_second = |time: Duration| ray_count as f64 / time.as_secs_f64() / 1e6;
+            println!(
+                "{:<12} {:<8} {:>8.2} Mray/s {:>8.2} Mray/s {:>7.2}x",
+                name,
+                format!("{coherence:?}"),
+                rays_per_second(scalar),
+                rays_per_second(packet),
+                scalar.as_secs_f64() / packet.as_secs_f64()
+            );
+        }
+    }
+}
+
+fn camera_ray_packets(camera: &Camera, coherence: Coherence) -> Vec<RayPacket> {
+    let dimensions = camera.dimensions();
+    let mut packets = Vec::new();
+    match coherence {
+        Coherence::Pixels => {
+            for block_y in (0..dimensions.height).step_by(2) {
+                for block_x in (0..dimensions.width).step_by(PACKET_SIZE / 2) {
+                    let rays: Vec<_> = (block_y..(block_y + 2).min(dimensions.height))
+                        .flat_map(|y| {
+                            (block_x..(block_x + PACKET_SIZE as u32 / 2).min(dimensions.width))
+                                .map(move |x| (x, y))
+                        })
+                        .map(|(x, y)| camera.create_ray_around_pixel(x, y, &Vector2::zeros()))
+                        .collect();
+                    packets.push(RayPacket::new(&rays));
+                }
+            }
+        }
+        Coherence::Samples => {
+            for y in 0..dimensions.height {
+                for x in 0..dimensions.width {
+                    let rays: Vec<_> = (0..PACKET_SIZE)
+                        .map(|_| {
+                            let offset = sampler::get_2d() - Vector2::new(0.5, 0.5);
+                            camera.create_ray_around_pixel(x, y, &offset)
+                        })
+                        .collect();
+                    packets.push(RayPacket::new(&rays));
+                }
+            }
+        }
+    }
+    packets
+}
+
+fn fastest_run<F: FnMut()>(mut run: F) -> Duration {
+    // The first run only warms up caches
+    run();
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
@@ -1,42 +1,606 @@
-use anyhow::{bail, Error, Result};
+use std::time::Duration;
 
-use crate::scene::{scene_generator, Scene};
+use anyhow::{bail, Context, Result};
+use clap::{Parser, ValueEnum};
+use nalgebra::Point3;
+
+use crate::{
+    core::{
+        aov::Aov,
+        bvh::BvhTree,
+        camera::{AdaptiveSampling, Camera, CameraOverrides, RenderSettings},
+        denoiser::Denoiser,
+        filter::{Filter, FilterKind},
+        tone_mapping::{ToneMapping, ToneMappingOperator, TransferFunction},
+    },
+    integrator::{
+        ambient_occlusion::AmbientOcclusion,
+        debug_view::{DebugProperty, DebugView},
+        path_tracer::PathTracer,
+    },
+    sampler::{
+        blue_noise::BlueNoiseSampler, halton::HaltonSampler, independent::IndependentSampler,
+        sobol::SobolSampler, stratified::StratifiedSampler,
+    },
+    scene::{scene_generator, scene_loader, Scene},
+    utils::rng,
+};
+
+pub enum Command {
+    Render(Box<Args>),
+    ListScenes,
+}
 
 pub struct Args {
     pub scene: Scene,
     pub output_path: String,
+    pub tone_mapping: ToneMapping,
+    pub render_settings: RenderSettings,
+    pub preview: Option<Preview>,
+    pub checkpoint: Option<CheckpointOptions>,
+    pub heatmap_path: Option<String>,
+    /// AOVs saved alongside the output image, `render_settings` may include more of them,
+    /// which are needed by the `denoiser`
+    pub saved_aovs: Vec<Aov>,
+    /// If set, output and preview images are denoised before saving
+    pub denoiser: Option<Denoiser>,
+}
+
+/// Periodically written checkpoint, from which the render can be resumed.
+pub struct CheckpointOptions {
+    pub path: String,
+    pub interval: Duration,
+    /// Whether to continue from the existing checkpoint at `path`
+    pub resume: bool,
+}
+
+/// Periodically written image with the current state of the render.
+pub struct Preview {
+    pub path: String,
+    pub interval: Duration,
 }
 
-pub fn parse_args(cli_args: &[String]) -> Result<Args> {
-    if cli_args.len() != 3 && cli_args.len() != 4 {
-        bail!("invalid number of arguments");
+/// Scene preset that can be selected by its name from the command line.
+pub struct ScenePreset {
+    pub name: &'static str,
+    pub description: &'static str,
+    build: fn(Option<u32>, usize) -> Scene,
+}
+
+pub const SCENE_PRESETS: &[ScenePreset] = &[
+    ScenePreset {
+        name: "spheres",
+        description: "Final scene of \"Ray Tracing in One Weekend\" with a grid of random spheres",
+        build: |samples_per_pixel, max_leaf_size| {
+            scene_generator::scene_with_spheres(24, 24, samples_per_pixel, max_leaf_size)
+        },
+    },
+    ScenePreset {
+        name: "moving_spheres",
+        description: "Random spheres scene with motion blur",
+        build: |samples_per_pixel, max_leaf_size| {
+            scene_generator::scene_with_moving_spheres(24, 24, samples_per_pixel, max_leaf_size)
+        },
+    },
+    ScenePreset {
+        name: "two_checker",
+        description: "Two spheres with checker texture",
+        build: scene_generator::scene_with_two_checker_spheres,
+    },
+    ScenePreset {
+        name: "earthmap",
+        description: "Sphere with image texture",
+        build: scene_generator::scene_with_earthmap,
+    },
+    ScenePreset {
+        name: "perlin_noise",
+        description: "Spheres with Perlin noise texture",
+        build: scene_generator::scene_with_perlin_noise,
+    },
+    ScenePreset {
+        name: "quads",
+        description: "Five colored quads",
+        build: scene_generator::scene_with_quads,
+    },
+    ScenePreset {
+        name: "simple_light",
+        description: "Perlin noise spheres lit by a sphere and a quad light",
+        build: scene_generator::scene_with_simple_light,
+    },
+    ScenePreset {
+        name: "cornell_box",
+        description: "Cornell box with two boxes",
+        build: scene_generator::scene_with_cornell_box,
+    },
+    ScenePreset {
+        name: "fog_cornell_box",
+        description: "Cornell box with smoke and fog boxes",
+        build: scene_generator::scene_with_fog_cornell_box,
+    },
+    ScenePreset {
+        name: "all_effects",
+        description: "Final scene of \"Ray Tracing: The Next Week\"",
+        build: scene_generator::scene_with_all_effects,
+    },
+    ScenePreset {
+        name: "triangles",
+        description: "Triangle meshes with smooth shading and texture coordinates",
+        build: scene_generator::scene_with_triangles,
+    },
+];
+
+/// Integrator selected with `--integrator`.
+#[derive(Clone, Copy, ValueEnum)]
+enum IntegratorKind {
+    /// Path tracing with all the lighting
+    Path,
+    /// Ambient occlusion of the first hit, limited by `--ao-distance`
+    AmbientOcclusion,
+    /// Outward normal of the first hit
+    Normal,
+    /// Base color of the first hit material
+    Albedo,
+    /// Distance of the first hit from the camera
+    Depth,
+    /// Texture coordinates of the first hit
+    Uv,
+    /// Distinct color for every material
+    MaterialId,
+    /// Number of BVH nodes visited by camera rays, red at `--max-bvh-cost`
+    BvhCost,
+}
+
+/// Sampler selected with `--sampler`.
+#[derive(Clone, Copy, ValueEnum)]
+enum SamplerKind {
+    /// Independent random values
+    Independent,
+    /// Jittered strata, one per sample
+    Stratified,
+    /// Randomly shifted Halton sequence
+    Halton,
+    /// Owen-scrambled Sobol sequence
+    Sobol,
+    /// Blue noise mask across pixels, golden ratio sequence across samples
+    BlueNoise,
+}
+
+/// Renders OUTPUT image of the SCENE, which is either name of the built-in scene or path to a `.toml` scene file.
+///
+/// If OUTPUT ends with `.toml`, the scene is exported into the scene file instead of being rendered.
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    /// Path of the output image (or scene file)
+    #[arg(required_unless_present = "list_scenes")]
+    output: Option<String>,
+
+    /// Name of the built-in scene or path to a scene file
+    #[arg(required_unless_present = "list_scenes")]
+    scene: Option<String>,
+
+    /// Print names of the built-in scenes and exit
+    #[arg(long)]
+    list_scenes: bool,
+
+    /// Number of samples per pixel
+    #[arg(short, long)]
+    samples_per_pixel: Option<u32>,
+
+    /// Width of the image in pixels
+    #[arg(long)]
+    width: Option<u32>,
+
+    /// Ratio of image width to its height
+    #[arg(long)]
+    aspect_ratio: Option<f64>,
+
+    /// Maximum number of ray bounces
+    #[arg(long)]
+    max_bounce_depth: Option<u32>,
+
+    /// Vertical field of view in degrees
+    #[arg(long)]
+    fov: Option<f64>,
+
+    /// Position of the camera, as `x,y,z`
+    #[arg(long, value_parser = parse_point, allow_hyphen_values = true)]
+    center: Option<Point3<f64>>,
+
+    /// Point the camera is looking at, as `x,y,z`
+    #[arg(long, value_parser = parse_point, allow_hyphen_values = true)]
+    look_at: Option<Point3<f64>>,
+
+    /// Variation angle of rays through each pixel in degrees
+    #[arg(long)]
+    defocus_angle: Option<f64>,
+
+    /// Distance from the camera to the plane of perfect focus
+    #[arg(long)]
+    focus_distance: Option<f64>,
+
+    /// Seed of the random numbers, the same seed always produces the same image.
+    /// Built-in scenes use it for generating their content as well
+    #[arg(long)]
+    seed: Option<u32>,
+
+    /// Number of threads used for rendering and building BVH, defaults to the number of logical CPUs
+    #[arg(short = 'j', long)]
+    threads: Option<usize>,
+
+    /// Size of the square tiles the image is rendered in
+    #[arg(long, default_value_t = 32)]
+    tile_size: u32,
+
+    /// Render in passes of increasing number of samples per pixel (1, 2, 4, ...)
+    #[arg(long)]
+    progressive: bool,
+
+    /// Path of the preview image, written with the current state of the render after
+    /// the first pass and then at most once per `--preview-interval`
+    #[arg(long, requires = "progressive")]
+    preview: Option<String>,
+
+    /// Minimum number of seconds between two preview writes
+    #[arg(long, default_value_t = 10.0)]
+    preview_interval: f64,
+
+    /// Maximum number of samples per pixel rendered in a single pass
+    #[arg(long)]
+    max_pass_samples: Option<u32>,
+
+    /// Path of the checkpoint file, written between passes at most once per `--checkpoint-interval`
+    /// and after the last one. Unless `--progressive`, `--adaptive-threshold` or `--max-pass-samples`
+    /// is used, passes are limited to 16 samples per pixel
+    #[arg(long)]
+    checkpoint: Option<String>,
+
+    /// Minimum number of seconds between two checkpoint writes
+    #[arg(long, default_value_t = 60.0)]
+    checkpoint_interval: f64,
+
+    /// Continue the render from `--checkpoint` file, number of samples per pixel can be increased,
+    /// but the rest of the scene and the integrator, sampler, filter and AOVs must be the same
+    #[arg(long, requires = "checkpoint")]
+    resume: bool,
+
+    /// Enable adaptive sampling: pixels stop being sampled once standard error of their luminance
+    /// falls under this fraction of the luminance, `--samples-per-pixel` becomes the maximum
+    #[arg(long)]
+    adaptive_threshold: Option<f64>,
+
+    /// Minimum number of samples per pixel with adaptive sampling
+    #[arg(long, default_value_t = 16, requires = "adaptive_threshold")]
+    min_samples_per_pixel: u32,
+
+    /// Path of the image showing number of samples used by every pixel
+    #[arg(long)]
+    heatmap: Option<String>,
+
+    /// Disable direct sampling of lights, light is then found only by random bounces
+    #[arg(long)]
+    no_light_sampling: bool,
+
+    /// Number of bounces after which paths are terminated with Russian roulette
+    #[arg(long, default_value_t = 3)]
+    russian_roulette_depth: u32,
+
+    /// Disable Russian roulette, paths are then terminated only at the maximum bounce depth
+    #[arg(long, conflicts_with = "russian_roulette_depth")]
+    no_russian_roulette: bool,
+
+    /// Algorithm computing the image, all but `path` are debug views of the scene geometry
+    #[arg(long, value_enum, default_value_t = IntegratorKind::Path)]
+    integrator: IntegratorKind,
+
+    /// Maximum distance of occluders with `ambient-occlusion` integrator
+    #[arg(long, default_value_t = f64::INFINITY)]
+    ao_distance: f64,
+
+    /// Number of visited BVH nodes rendered as red with `bvh-cost` integrator
+    #[arg(long, default_value_t = 64)]
+    max_bvh_cost: u32,
+
+    /// Maximum number of objects in a leaf of the scene BVH, defaults to 4. Smaller leaves
+    /// are still split where the surface area heuristic expects it to make hitting them cheaper
+    #[arg(long)]
+    bvh_leaf_size: Option<usize>,
+
+    /// Generator of the random values used by samples, all but `independent` spread samples
+    /// of every pixel evenly
+    #[arg(long, value_enum, default_value_t = SamplerKind::Independent)]
+    sampler: SamplerKind,
+
+    /// Pixel reconstruction filter, samples are splatted into all pixels within its radius
+    #[arg(long, value_enum, default_value_t = FilterKind::Box)]
+    filter: FilterKind,
+
+    /// Radius of the reconstruction filter in pixels, defaults to 0.5 for `box`, 1 for `tent`,
+    /// 1.5 for `gaussian` and 2 for `mitchell` and `lanczos`
+    #[arg(long)]
+    filter_radius: Option<f64>,
+
+    /// AOVs saved alongside the main image, each into a separate file (`image.exr`
+    /// becomes `image.albedo.exr`)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    aov: Vec<Aov>,
+
+    /// Denoise output and preview images, albedo and normal AOVs are rendered for it
+    #[arg(long)]
+    denoise: bool,
+
+    /// Number of iterations of the denoising filter, each doubles its radius
+    #[arg(long, default_value_t = 3, requires = "denoise")]
+    denoise_iterations: u32,
+
+    /// Tone mapping operator used for 8-bit output formats
+    #[arg(long, value_enum, default_value_t = ToneMappingOperator::Clamp)]
+    tonemap: ToneMappingOperator,
+
+    /// Exposure adjustment in stops, applied before tone mapping
+    #[arg(long, default_value_t = 0.0, allow_hyphen_values = true)]
+    exposure: f64,
+
+    /// Smallest radiance mapped to white by `extended-reinhard` operator
+    #[arg(long, default_value_t = 4.0)]
+    white_point: f64,
+
+    /// Gamma used to encode tone mapped values
+    #[arg(long, default_value_t = 2.0, conflicts_with = "srgb")]
+    gamma: f64,
+
+    /// Encode tone mapped values with sRGB transfer function instead of gamma
+    #[arg(long)]
+    srgb: bool,
+}
+
+/// Parses command line arguments, prints help or usage error and exits the process on invalid input.
+pub fn parse_args(cli_args: &[String]) -> Result<Command> {
+    let cli = Cli::try_parse_from(cli_args).unwrap_or_else(|e| e.exit());
+
+    if cli.list_scenes {
+        return Ok(Command::ListScenes);
+    }
+
+    // Both are required by clap unless `--list-scenes` is present
+    let (Some(output_path), Some(scene_name)) = (cli.output, cli.scene) else {
+        bail!("missing output path or scene");
+    };
+
+    if cli.threads == Some(0) {
+        bail!("number of threads must be positive");
+    }
+    // Pool is needed already for building BVH of the scene
+    if let Some(threads) = cli.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .context("cannot create thread pool")?;
     }
 
-    let output_path = cli_args[1].clone();
+    // Checked before the scene is built, its BVH is built only once
+    let max_leaf_size = cli.bvh_leaf_size.unwrap_or(BvhTree::DEFAULT_MAX_LEAF_SIZE);
+    if !(1..=BvhTree::MAX_LEAF_SIZE).contains(&max_leaf_size) {
+        bail!(
+            "BVH leaf size must be between 1 and {}",
+            BvhTree::MAX_LEAF_SIZE
+        );
+    }
+
+    // Random content of the built-in scenes is generated from the seed too,
+    // scene files reseed the generator with their own seed while loading
+    rng::reseed(cli.seed.unwrap_or(rng::DEFAULT_SEED).into());
 
-    let samples_per_pixel = if cli_args.len() == 4 {
-        Some(
-            cli_args[3]
-                .parse::<u32>()
-                .map_err(|_| Error::msg("invalid 'samples_per_pixel' value"))?,
-        )
+    let mut scene = if scene_name.ends_with(".toml") {
+        scene_loader::load_scene(&scene_name, cli.samples_per_pixel, max_leaf_size)?
     } else {
-        None
+        let preset = SCENE_PRESETS
+            .iter()
+            .find(|preset| preset.name == scene_name)
+            .with_context(|| {
+                format!("unknown scene '{scene_name}', use --list-scenes to see available ones")
+            })?;
+        (preset.build)(cli.samples_per_pixel, max_leaf_size)
+    };
+
+    let overrides = CameraOverrides {
+        width: cli.width,
+        aspect_ratio: cli.aspect_ratio,
+        samples_per_pixel: cli.samples_per_pixel,
+        max_bounce_depth: cli.max_bounce_depth,
+        vertical_fov_angles: cli.fov,
+        center: cli.center,
+        look_at: cli.look_at,
+        defocus_angle: cli.defocus_angle,
+        focus_distance: cli.focus_distance,
+        seed: cli.seed,
+    };
+    validate_overrides(&overrides, scene.camera())?;
+    scene.override_camera(&overrides);
+
+    if cli.gamma <= 0.0 {
+        bail!("gamma must be positive");
+    }
+    if cli.white_point <= 0.0 {
+        bail!("white point must be positive");
+    }
+    if cli.tile_size == 0 {
+        bail!("tile size must be positive");
+    }
+    if cli.preview_interval < 0.0 {
+        bail!("preview interval cannot be negative");
+    }
+    if cli.max_pass_samples == Some(0) {
+        bail!("maximum number of samples in a pass must be positive");
+    }
+    if cli.checkpoint_interval < 0.0 {
+        bail!("checkpoint interval cannot be negative");
+    }
+    // Single pass would write checkpoint only after the whole render is done
+    const CHECKPOINT_PASS_SAMPLES: u32 = 16;
+    let has_passes = cli.progressive || cli.adaptive_threshold.is_some();
+    let max_pass_samples = match cli.max_pass_samples {
+        None if cli.checkpoint.is_some() && !has_passes => Some(CHECKPOINT_PASS_SAMPLES),
+        max_pass_samples => max_pass_samples,
+    };
+    if cli
+        .adaptive_threshold
+        .is_some_and(|threshold| threshold <= 0.0)
+    {
+        bail!("adaptive sampling threshold must be positive");
+    }
+    if cli.min_samples_per_pixel < 2 {
+        bail!("minimum number of samples per pixel must be at least 2");
+    }
+    let adaptive_sampling = cli
+        .adaptive_threshold
+        .map(|threshold| AdaptiveSampling::new(cli.min_samples_per_pixel, threshold));
+    if cli.ao_distance <= 0.0 {
+        bail!("ambient occlusion distance must be positive");
+    }
+    if cli.max_bvh_cost == 0 {
+        bail!("maximum BVH cost must be positive");
+    }
+    let debug_view = |property| DebugView::new(property, cli.max_bvh_cost).into();
+    let integrator = match cli.integrator {
+        IntegratorKind::Path => PathTracer::builder()
+            .light_sampling(!cli.no_light_sampling)
+            .russian_roulette_depth(
+                (!cli.no_russian_roulette).then_some(cli.russian_roulette_depth),
+            )
+            .build()
+            .into(),
+        IntegratorKind::AmbientOcclusion => AmbientOcclusion::new(cli.ao_distance).into(),
+        IntegratorKind::Normal => debug_view(DebugProperty::Normal),
+        IntegratorKind::Albedo => debug_view(DebugProperty::Albedo),
+        IntegratorKind::Depth => debug_view(DebugProperty::Depth),
+        IntegratorKind::Uv => debug_view(DebugProperty::Uv),
+        IntegratorKind::MaterialId => debug_view(DebugProperty::MaterialId),
+        IntegratorKind::BvhCost => debug_view(DebugProperty::BvhCost),
+    };
+    let sampler = match cli.sampler {
+        SamplerKind::Independent => IndependentSampler.into(),
+        SamplerKind::Stratified => StratifiedSampler.into(),
+        SamplerKind::Halton => HaltonSampler.into(),
+        SamplerKind::Sobol => SobolSampler.into(),
+        SamplerKind::BlueNoise => BlueNoiseSampler.into(),
     };
+    if cli.filter_radius.is_some_and(|radius| radius <= 0.0) {
+        bail!("filter radius must be positive");
+    }
+    let filter = Filter::new(
+        cli.filter,
+        cli.filter_radius
+            .unwrap_or_else(|| cli.filter.default_radius()),
+    );
+    // Every AOV is rendered once, even if it's listed multiple times
+    let saved_aovs = cli.aov.iter().fold(vec![], |mut unique, aov| {
+        if !unique.contains(aov) {
+            unique.push(*aov);
+        }
+        unique
+    });
+    let denoiser = cli.denoise.then(|| {
+        Denoiser::builder()
+            .iterations(cli.denoise_iterations)
+            .build()
+    });
+    let mut aovs = saved_aovs.clone();
+    if denoiser.is_some() {
+        for feature in [Aov::Albedo, Aov::Normal] {
+            if !aovs.contains(&feature) {
+                aovs.push(feature);
+            }
+        }
+    }
+    let render_settings = RenderSettings::builder()
+        .tile_size(cli.tile_size)
+        .progressive(cli.progressive)
+        .max_pass_samples(max_pass_samples)
+        .adaptive_sampling(adaptive_sampling)
+        .integrator(integrator)
+        .filter(filter)
+        .sampler(sampler)
+        .aovs(aovs)
+        .build();
+    let checkpoint = cli.checkpoint.map(|path| CheckpointOptions {
+        path,
+        interval: Duration::from_secs_f64(cli.checkpoint_interval),
+        resume: cli.resume,
+    });
+    let preview = cli.preview.map(|path| Preview {
+        path,
+        interval: Duration::from_secs_f64(cli.preview_interval),
+    });
 
-    let scene = match cli_args[2].as_str() {
-        "spheres" => scene_generator::scene_with_spheres(24, 24, samples_per_pixel),
-        "moving_spheres" => scene_generator::scene_with_moving_spheres(24, 24, samples_per_pixel),
-        "two_checker" => scene_generator::scene_with_two_checker_spheres(samples_per_pixel),
-        "earthmap" => scene_generator::scene_with_earthmap(samples_per_pixel),
-        "perlin_noise" => scene_generator::scene_with_perlin_noise(samples_per_pixel),
-        "quads" => scene_generator::scene_with_quads(samples_per_pixel),
-        "simple_light" => scene_generator::scene_with_simple_light(samples_per_pixel),
-        "cornell_box" => scene_generator::scene_with_cornell_box(samples_per_pixel),
-        "fog_cornell_box" => scene_generator::scene_with_fog_cornell_box(samples_per_pixel),
-        "all_effects" => scene_generator::scene_with_all_effects(samples_per_pixel),
-        _ => bail!("unknown scene"),
+    let transfer = if cli.srgb {
+        TransferFunction::Srgb
+    } else {
+        TransferFunction::Gamma(cli.gamma)
     };
+    let tone_mapping = ToneMapping::builder()
+        .operator(cli.tonemap)
+        .exposure(cli.exposure)
+        .white_point(cli.white_point)
+        .transfer(transfer)
+        .build();
 
-    Ok(Args { scene, output_path })
+    Ok(Command::Render(Box::new(Args {
+        scene,
+        output_path,
+        tone_mapping,
+        render_settings,
+        preview,
+        checkpoint,
+        heatmap_path: cli.heatmap,
+        saved_aovs,
+        denoiser,
+    })))
+}
+
+/// Checks `overrides` the same way scene files are checked, including the values they
+/// make the `camera` end up with.
+fn validate_overrides(overrides: &CameraOverrides, camera: &Camera) -> Result<()> {
+    if overrides.width == Some(0) {
+        bail!("width must be positive");
+    }
+    if overrides.samples_per_pixel == Some(0) {
+        bail!("number of samples per pixel must be positive");
+    }
+    if overrides.aspect_ratio.is_some_and(|ratio| ratio <= 0.0) {
+        bail!("aspect ratio must be positive");
+    }
+    if overrides
+        .vertical_fov_angles
+        .is_some_and(|fov| fov <= 0.0 || fov >= 180.0)
+    {
+        bail!("field of view must be between 0 and 180 degrees");
+    }
+    if overrides.defocus_angle.is_some_and(|angle| angle < 0.0) {
+        bail!("defocus angle cannot be negative");
+    }
+    if overrides
+        .focus_distance
+        .is_some_and(|distance| distance <= 0.0)
+    {
+        bail!("focus distance must be positive");
+    }
+    let center = overrides.center.unwrap_or(*camera.center());
+    let look_at = overrides.look_at.unwrap_or(*camera.look_at());
+    if center == look_at {
+        bail!("camera center and look at must differ");
+    }
+    Ok(())
+}
+
+fn parse_point(value: &str) -> Result<Point3<f64>> {
+    let coordinates = value
+        .split(',')
+        .map(|coordinate| coordinate.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .context("coordinates must be numbers")?;
+    match coordinates[..] {
+        [x, y, z] => Ok(Point3::new(x, y, z)),
+        _ => bail!("expected 3 comma separated coordinates"),
+    }
 }
@@ -11,12 +11,17 @@ pub struct AxisAlignedBoundingBox {
     interval_z: RangeInclusive<f64>,
 }
 
+#[derive(Clone, Copy)]
 pub enum Axis {
     X,
     Y,
     Z,
 }
 
+impl Axis {
+    pub const ALL: [Axis; 3] = [Axis::X, Axis::Y, Axis::Z];
+}
+
 impl AxisAlignedBoundingBox {
     pub fn new(start: Point3<f64>, end: Point3<f64>) -> Self {
         let interval_x = Self::expand_to_mininimum(Self::find_interval(start.x, end.x));
@@ -83,6 +88,22 @@ impl AxisAlignedBoundingBox {
         }
     }
 
+    pub fn surface_area(&self) -> f64 {
+        let len_x = self.interval_x.end() - self.interval_x.start();
+        let len_y = self.interval_y.end() - self.interval_y.start();
+        let len_z = self.interval_z.end() - self.interval_z.start();
+        2.0 * (len_x * len_y + len_y * len_z + len_z * len_x)
+    }
+
+    pub fn centroid(&self) -> Point3<f64> {
+        let center = |interval: &RangeInclusive<f64>| (interval.start() + interval.end()) / 2.0;
+        Point3::new(
+            center(&self.interval_x),
+            center(&self.interval_y),
+            center(&self.interval_z),
+        )
+    }
+
     fn find_interval(u: f64, v: f64) -> RangeInclusive<f64> {
         let start = u.min(v);
         let end = u.max(v);
@@ -94,7 +115,7 @@ impl AxisAlignedBoundingBox {
         let mut t_max = *t_range.end();
 
         let (t_min_x, t_max_x) =
-            self.intersects_ray_in_axis(ray.direction().x, ray.origin().x, &Axis::X);
+            self.intersects_ray_in_axis(ray.inverse_direction().x, ray.origin().x, &Axis::X);
 
         t_min = t_min.max(t_min_x);
         t_max = t_max.min(t_max_x);
@@ -104,7 +125,7 @@ impl AxisAlignedBoundingBox {
         }
 
         let (t_min_y, t_max_y) =
-            self.intersects_ray_in_axis(ray.direction().y, ray.origin().y, &Axis::Y);
+            self.intersects_ray_in_axis(ray.inverse_direction().y, ray.origin().y, &Axis::Y);
 
         t_min = t_min.max(t_min_y);
         t_max = t_max.min(t_max_y);
@@ -114,7 +135,7 @@ impl AxisAlignedBoundingBox {
         }
 
         let (t_min_z, t_max_z) =
-            self.intersects_ray_in_axis(ray.direction().z, ray.origin().z, &Axis::Z);
+            self.intersects_ray_in_axis(ray.inverse_direction().z, ray.origin().z, &Axis::Z);
 
         t_min = t_min.max(t_min_z);
         t_max = t_max.min(t_max_z);
@@ -150,7 +171,7 @@ impl AxisAlignedBoundingBox {
     /// we can assume that `t0 < t1`
     fn intersects_ray_in_axis(
         &self,
-        ray_direction: f64,
+        ray_inverse_direction: f64,
         ray_origin: f64,
         axis: &Axis,
     ) -> (f64, f64) {
@@ -160,8 +181,8 @@ impl AxisAlignedBoundingBox {
             Axis::Z => &self.interval_z,
         };
 
-        let t0 = (interval.start() - ray_origin) / ray_direction;
-        let t1 = (interval.end() - ray_origin) / ray_direction;
+        let t0 = (interval.start() - ray_origin) * ray_inverse_direction;
+        let t1 = (interval.end() - ray_origin) * ray_inverse_direction;
 
         let t_min = t0.min(t1);
         let t_max = t0.max(t1);
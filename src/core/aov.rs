@@ -0,0 +1,128 @@
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use rgb::Rgb;
+
+use crate::{
+    integrator::CameraRay,
+    material::{Material, MaterialType},
+    object::hittable_object::HitRecord,
+    scene::SceneContent,
+    utils::color_map,
+};
+
+/// Arbitrary output variable, rendered alongside the main image.
+///
+/// Properties of the first hit are black where camera rays hit nothing. Light AOVs
+/// (`Emission`, `Direct` and `Indirect`) sum up to the main image.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+pub enum Aov {
+    /// Base color of the first hit material
+    Albedo,
+    /// Outward normal of the first hit in world space
+    Normal,
+    /// Distance of the first hit from the camera
+    Depth,
+    /// Position of the first hit in world space
+    Position,
+    /// Distinct color for every material of the first hit
+    MaterialId,
+    /// Light emitted by the first hit object (or background seen directly)
+    Emission,
+    /// Light which reached the first hit directly from emitters or background
+    Direct,
+    /// Light which reached the first hit after bouncing at least once
+    Indirect,
+}
+
+impl Aov {
+    pub const ALL: [Aov; 8] = [
+        Aov::Albedo,
+        Aov::Normal,
+        Aov::Depth,
+        Aov::Position,
+        Aov::MaterialId,
+        Aov::Emission,
+        Aov::Direct,
+        Aov::Indirect,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aov::Albedo => "albedo",
+            Aov::Normal => "normal",
+            Aov::Depth => "depth",
+            Aov::Position => "position",
+            Aov::MaterialId => "material_id",
+            Aov::Emission => "emission",
+            Aov::Direct => "direct",
+            Aov::Indirect => "indirect",
+        }
+    }
+
+    /// Returns whether AOV holds light (which should be tone mapped like the main image)
+    /// rather than other data.
+    pub fn is_light(&self) -> bool {
+        matches!(self, Aov::Emission | Aov::Direct | Aov::Indirect)
+    }
+
+    /// Returns path of the file storing this AOV of the image saved to `image_path`,
+    /// `image.exr` becomes `image.albedo.exr`.
+    pub fn path<P: AsRef<Path>>(&self, image_path: P) -> PathBuf {
+        let image_path = image_path.as_ref();
+        let mut file_name = image_path.file_stem().unwrap_or_default().to_owned();
+        file_name.push(".");
+        file_name.push(self.name());
+        if let Some(extension) = image_path.extension() {
+            file_name.push(".");
+            file_name.push(extension);
+        }
+        image_path.with_file_name(file_name)
+    }
+}
+
+/// Values of all AOVs of a single sample.
+#[derive(Clone, Copy)]
+pub struct AovSample {
+    values: [Rgb<f64>; Aov::ALL.len()],
+}
+
+impl AovSample {
+    pub fn get(&self, aov: Aov) -> Rgb<f64> {
+        self.values[aov as usize]
+    }
+
+    pub fn add(&mut self, aov: Aov, value: Rgb<f64>) {
+        self.values[aov as usize] += value;
+    }
+
+    /// Records properties of the object hit by the camera `ray`.
+    pub fn record_hit(&mut self, hit_record: &HitRecord, material: &MaterialType) {
+        let normal = hit_record.outward_normal();
+        let pos = hit_record.pos();
+        let t = hit_record.t();
+        self.values[Aov::Albedo as usize] = material.albedo(hit_record);
+        self.values[Aov::Normal as usize] = Rgb::new(normal.x, normal.y, normal.z);
+        self.values[Aov::Depth as usize] = Rgb::new(t, t, t);
+        self.values[Aov::Position as usize] = Rgb::new(pos.x, pos.y, pos.z);
+        self.values[Aov::MaterialId as usize] = color_map::id_color(hit_record.material_id());
+    }
+
+    /// Records properties of the object hit by the `camera_ray`, see `record_hit`.
+    pub fn record_first_hit(&mut self, camera_ray: &CameraRay, scene_content: &SceneContent) {
+        if let Some(hit_record) = camera_ray.hit() {
+            let material = scene_content
+                .material_by_id(hit_record.material_id())
+                .unwrap();
+            self.record_hit(hit_record, material);
+        }
+    }
+}
+
+impl Default for AovSample {
+    fn default() -> Self {
+        AovSample {
+            values: [Rgb::new(0.0, 0.0, 0.0); Aov::ALL.len()],
+        }
+    }
+}
@@ -1,214 +1,923 @@
-use std::ops::RangeInclusive;
+use std::{borrow::Borrow, fmt, ops::RangeInclusive, sync::Arc};
+
+use nalgebra::Point3;
+use rayon::prelude::*;
 
 use crate::{
-    core::aabb::AxisAlignedBoundingBox,
+    core::aabb::{Axis, AxisAlignedBoundingBox},
     object::{
         hittable_object::{HitRecord, HittableObject},
         hittable_objects_list::HittableObjectsList,
         HittableObjectType,
     },
-    ray::Ray,
+    ray::{
+        ray_packet::{RayPacket, PACKET_SIZE},
+        Ray,
+    },
 };
 
-pub struct BvhTree {
-    /// Collection of all nodes in a tree. Root is always at index 0.
-    nodes: Vec<BvhValue>,
+/// Bounding volume hierarchy built with the surface area heuristic (SAH): objects are split
+/// where the expected cost of hitting the subtrees, estimated from the areas of their bounding
+/// boxes, is the lowest.
+///
+/// Objects are stored as `T`, which is either the object itself or a reference shared
+/// with its other owner, like `Arc` for the objects kept by the scene. Lists have to be
+/// flattened before building, the tree doesn't look into them.
+pub struct BvhTree<T = HittableObjectType> {
+    /// Nodes in depth-first order, so the first child of every inner node is right after it.
+    /// Root is always at index 0.
+    nodes: Vec<LinearNode>,
+    /// Objects sorted so that every leaf holds a contiguous range of them
+    objects: Vec<T>,
+    bounding_box: AxisAlignedBoundingBox,
 }
 
 impl BvhTree {
-    fn root(&self) -> &BvhValue {
-        &self.nodes[0]
+    pub const DEFAULT_MAX_LEAF_SIZE: usize = 4;
+    /// Largest number of objects which fits into a leaf
+    pub const MAX_LEAF_SIZE: usize = u16::MAX as usize;
+    /// Number of bins centroids are sorted into when looking for the best split
+    const BIN_COUNT: usize = 16;
+    /// Costs of visiting a node and hitting an object, used to compare splits
+    const TRAVERSAL_COST: f64 = 1.0;
+    const INTERSECTION_COST: f64 = 1.0;
+    /// Subtrees with at least this many objects are built in parallel
+    const PARALLEL_BUILD_THRESHOLD: usize = 4096;
+    /// Deeper nodes are split in half instead of by SAH, which keeps the depth of the tree
+    /// (with less than 2^32 objects) within `TRAVERSAL_STACK_SIZE`
+    const MAX_SAH_DEPTH: usize = 32;
+    /// Number of nodes waiting to be visited during traversal, at most one per level of the tree
+    const TRAVERSAL_STACK_SIZE: usize = 64;
+
+    /// Builds subtree of `primitives` at `depth` (1 for the root) and sorts them in the order
+    /// of its leaves, `first` is the index of the first one in the whole tree.
+    ///
+    /// Primitives are split at the cheapest of the SAH splits found by `find_split`, unless
+    /// a leaf would be cheaper and there are at most `max_leaf_size` of them. Large subtrees
+    /// are built in parallel.
+    fn build(
+        primitives: &mut [Primitive],
+        first: usize,
+        depth: usize,
+        max_leaf_size: usize,
+    ) -> BuildNode {
+        let parallel = primitives.len() >= Self::PARALLEL_BUILD_THRESHOLD;
+        let bounds = if parallel {
+            primitives
+                .par_chunks(Self::PARALLEL_BUILD_THRESHOLD)
+                .map(Bounds::of)
+                .reduce_with(Bounds::merge)
+                .expect("subtree is never empty")
+        } else {
+            Bounds::of(primitives)
+        };
+
+        let count = primitives.len();
+        let leaf_cost = count as f64 * Self::INTERSECTION_COST;
+        let split = if depth < Self::MAX_SAH_DEPTH {
+            Self::find_split(primitives, &bounds, parallel)
+        } else {
+            None
+        };
+        let (axis, mid) = match split {
+            Some(split) if count > max_leaf_size || split.cost < leaf_cost => {
+                (split.axis, Self::partition(primitives, &split))
+            }
+            // All centroids are at the same point or the tree is too deep already,
+            // splitting in half at least limits the depth
+            _ if count > max_leaf_size => {
+                let axis = bounds.longest_centroid_axis();
+                let mid = count / 2;
+                primitives.select_nth_unstable_by(mid, |a, b| {
+                    a.centroid[axis as usize].total_cmp(&b.centroid[axis as usize])
+                });
+                (axis, mid)
+            }
+            _ => {
+                return BuildNode::Leaf {
+                    first,
+                    count,
+                    bounding_box: bounds.bounding_box,
+                }
+            }
+        };
+
+        let (left, right) = primitives.split_at_mut(mid);
+        let (left, right) = if parallel {
+            rayon::join(
+                || Self::build(left, first, depth + 1, max_leaf_size),
+                || Self::build(right, first + mid, depth + 1, max_leaf_size),
+            )
+        } else {
+            (
+                Self::build(left, first, depth + 1, max_leaf_size),
+                Self::build(right, first + mid, depth + 1, max_leaf_size),
+            )
+        };
+        BuildNode::Node {
+            left: Box::new(left),
+            right: Box::new(right),
+            axis,
+            bounding_box: bounds.bounding_box,
+        }
     }
 
-    fn hit_node(
-        &self,
-        node: &BvhValue,
-        ray: &Ray,
-        t_range: &RangeInclusive<f64>,
-    ) -> Option<HitRecord> {
+    /// Appends `node` and its subtree to `nodes` in depth-first order and returns its id.
+    fn flatten(node: BuildNode, nodes: &mut Vec<LinearNode>) -> usize {
+        let id = nodes.len();
         match node {
-            BvhValue::Node(node) => {
-                if !node.bounding_box().intersects_ray(ray, t_range) {
-                    return None;
-                }
+            BuildNode::Leaf {
+                first,
+                count,
+                bounding_box,
+            } => nodes.push(LinearNode::new(
+                &bounding_box,
+                first as u32,
+                count as u16,
+                0,
+            )),
+            BuildNode::Node {
+                left,
+                right,
+                axis,
+                bounding_box,
+            } => {
+                // Offset of the second child is known only after the first one is flattened
+                nodes.push(LinearNode::new(&bounding_box, 0, 0, axis as u8));
+                Self::flatten(*left, nodes);
+                nodes[id].offset = Self::flatten(*right, nodes) as u32;
+            }
+        }
+        id
+    }
 
-                let hit_left = self.hit_node(&self.nodes[node.left_id], ray, t_range);
+    /// Returns the cheapest split of `primitives` by the surface area heuristic, `None` if all
+    /// their centroids are at the same point.
+    ///
+    /// Centroids are sorted into `BIN_COUNT` bins along every axis and only splits between
+    /// the bins are considered, which is much faster than trying every primitive and almost
+    /// as good.
+    fn find_split(primitives: &[Primitive], bounds: &Bounds, parallel: bool) -> Option<SahSplit> {
+        let parent_area = bounds.bounding_box.surface_area();
 
-                let hit_right_max_t = if let Some(hr) = &hit_left {
-                    hr.t()
-                } else {
-                    *t_range.end()
-                };
+        let mut best: Option<SahSplit> = None;
+        for axis in Axis::ALL {
+            let (min, extent) = (
+                bounds.centroid_min[axis as usize],
+                bounds.centroid_max[axis as usize] - bounds.centroid_min[axis as usize],
+            );
+            if extent <= 0.0 {
+                continue;
+            }
 
-                let hit_right_range = *t_range.start()..=hit_right_max_t;
-                let hit_right = self.hit_node(&self.nodes[node.right_id], ray, &hit_right_range);
-                if hit_right.is_some() {
-                    return hit_right;
-                }
+            let bins = if parallel {
+                primitives
+                    .par_chunks(Self::PARALLEL_BUILD_THRESHOLD)
+                    .map(|chunk| Self::bin(chunk, axis, min, extent))
+                    .reduce(Default::default, |mut bins, other| {
+                        for (bin, other) in bins.iter_mut().zip(&other) {
+                            bin.merge(other);
+                        }
+                        bins
+                    })
+            } else {
+                Self::bin(primitives, axis, min, extent)
+            };
 
-                hit_left
+            // Sizes of the right sides of all splits, `i`-th split is right before `i`-th bin
+            let mut right_sides: [(usize, f64); Self::BIN_COUNT] = [(0, 0.0); Self::BIN_COUNT];
+            let mut right = Bin::default();
+            for bin in (1..Self::BIN_COUNT).rev() {
+                right.merge(&bins[bin]);
+                right_sides[bin] = (right.count, right.surface_area());
+            }
+
+            let mut left = Bin::default();
+            for bin in 1..Self::BIN_COUNT {
+                left.merge(&bins[bin - 1]);
+                let (right_count, right_area) = right_sides[bin];
+                if left.count == 0 || right_count == 0 {
+                    continue;
+                }
+                let cost = Self::TRAVERSAL_COST
+                    + Self::INTERSECTION_COST
+                        * (left.count as f64 * left.surface_area()
+                            + right_count as f64 * right_area)
+                        / parent_area;
+                if best.as_ref().is_none_or(|best| cost < best.cost) {
+                    best = Some(SahSplit {
+                        axis,
+                        bin,
+                        min,
+                        extent,
+                        cost,
+                    });
+                }
             }
-            BvhValue::Leaf(leaf) => leaf.hit(ray, t_range),
         }
+        best
     }
 
-    /// `end` is exclusive
-    fn split_into_nodes(
-        nodes: &mut Vec<BvhValue>,
-        objects: &mut [HittableObjectType],
-        start: usize,
-        end: usize,
-    ) -> (usize, usize) {
-        let diff = end - start;
+    /// Sorts `primitives` into bins dividing range of centroids starting at `min` on the `axis`.
+    fn bin(primitives: &[Primitive], axis: Axis, min: f64, extent: f64) -> [Bin; Self::BIN_COUNT] {
+        let mut bins: [Bin; Self::BIN_COUNT] = Default::default();
+        for primitive in primitives {
+            bins[Self::bin_index(primitive.centroid[axis as usize], min, extent)]
+                .add(&primitive.bounding_box);
+        }
+        bins
+    }
 
-        if diff == 1 {
-            let leaf = BvhLeaf {
-                object: objects[start].clone(),
-            };
-            nodes.push(BvhValue::Leaf(leaf));
-            let id = nodes.len() - 1;
-            return (id, id);
+    fn bin_index(centroid: f64, min: f64, extent: f64) -> usize {
+        (((centroid - min) / extent * Self::BIN_COUNT as f64) as usize).min(Self::BIN_COUNT - 1)
+    }
+
+    /// Moves primitives on the left side of the `split` to the beginning of `primitives`
+    /// and returns their number.
+    fn partition(primitives: &mut [Primitive], split: &SahSplit) -> usize {
+        let mut mid = 0;
+        for i in 0..primitives.len() {
+            let centroid = primitives[i].centroid[split.axis as usize];
+            if Self::bin_index(centroid, split.min, split.extent) < split.bin {
+                primitives.swap(i, mid);
+                mid += 1;
+            }
         }
+        mid
+    }
+}
 
-        if start + 1 == end {
-            let left_leaf = BvhLeaf {
-                object: objects[start].clone(),
-            };
-            nodes.push(BvhValue::Leaf(left_leaf));
-            let right_leaf = BvhLeaf {
-                object: objects[end].clone(),
-            };
-            nodes.push(BvhValue::Leaf(right_leaf));
-            let len = nodes.len();
-            return (len - 2, len - 1);
+impl<T: Borrow<HittableObjectType>> BvhTree<T> {
+    /// Builds tree of `objects`, leaves hold at most `max_leaf_size` of them.
+    pub fn new(objects: Vec<T>, max_leaf_size: usize) -> Self
+    where
+        T: Sync,
+    {
+        assert!(!objects.is_empty());
+        assert!((1..=BvhTree::MAX_LEAF_SIZE).contains(&max_leaf_size));
+
+        let mut primitives: Vec<Primitive> = objects
+            .par_iter()
+            .enumerate()
+            .map(|(index, object)| {
+                let bounding_box = object.borrow().bounding_box();
+                Primitive {
+                    index,
+                    bounding_box: bounding_box.clone(),
+                    centroid: bounding_box.centroid(),
+                }
+            })
+            .collect();
+        assert!(primitives.len() <= u32::MAX as usize);
+        let root = BvhTree::build(&mut primitives, 0, 1, max_leaf_size);
+        let bounding_box = root.bounding_box().clone();
+        let mut nodes = Vec::new();
+        BvhTree::flatten(root, &mut nodes);
+
+        // Objects are moved into the order of the leaves instead of being cloned into them
+        let mut objects: Vec<Option<T>> = objects.into_iter().map(Some).collect();
+        let objects = primitives
+            .iter()
+            .map(|primitive| {
+                objects[primitive.index]
+                    .take()
+                    .expect("every object is in exactly one leaf")
+            })
+            .collect();
+        Self {
+            nodes,
+            objects,
+            bounding_box,
         }
+    }
 
-        let mut bounding_box = AxisAlignedBoundingBox::empty();
+    /// Works the same as `hit`, but additionally returns number of visited nodes
+    /// (including leaves), which is the cost of the traversal.
+    pub fn hit_with_cost(
+        &self,
+        ray: &Ray,
+        t_range: &RangeInclusive<f64>,
+    ) -> (Option<HitRecord>, u32) {
+        let mut cost = 0;
+        let hit_record = self.hit_nodes(ray, t_range, &mut cost);
+        (hit_record, cost)
+    }
 
-        for obj in objects.iter().take(end).skip(start) {
-            bounding_box = AxisAlignedBoundingBox::merge(&bounding_box, obj.bounding_box());
+    /// Visits nodes hit by the `ray` until there are no closer objects left. Children are
+    /// visited in the order of the ray direction on their split axis, so the nearer one can
+    /// shorten the ray before the other one is checked.
+    fn hit_nodes(
+        &self,
+        ray: &Ray,
+        t_range: &RangeInclusive<f64>,
+        cost: &mut u32,
+    ) -> Option<HitRecord> {
+        let t_min = *t_range.start();
+        let mut t_max = *t_range.end();
+        let mut closest_hit: Option<HitRecord> = None;
+
+        let mut stack = [0u32; BvhTree::TRAVERSAL_STACK_SIZE];
+        let mut stack_size = 0;
+        let mut id = 0;
+        loop {
+            *cost += 1;
+            let node = &self.nodes[id];
+            if node.intersects_ray(ray, t_min, t_max) {
+                if node.object_count > 0 {
+                    let first = node.offset as usize;
+                    for object in &self.objects[first..first + node.object_count as usize] {
+                        if let Some(hit) = object.borrow().hit(ray, &(t_min..=t_max)) {
+                            t_max = hit.t();
+                            closest_hit = Some(hit);
+                        }
+                    }
+                } else {
+                    let (near, far) = if ray.direction()[node.axis as usize] < 0.0 {
+                        (node.offset as usize, id + 1)
+                    } else {
+                        (id + 1, node.offset as usize)
+                    };
+                    stack[stack_size] = far as u32;
+                    stack_size += 1;
+                    id = near;
+                    continue;
+                }
+            }
+            if stack_size == 0 {
+                return closest_hit;
+            }
+            stack_size -= 1;
+            id = stack[stack_size] as usize;
         }
+    }
 
-        let longest_axis = bounding_box.longest_axis();
-        objects[start..end].sort_by(|a, b| {
-            AxisAlignedBoundingBox::compare_by_axis(
-                a.bounding_box(),
-                b.bounding_box(),
-                &longest_axis,
-            )
-        });
+    /// Visits nodes hit by the `ray` until any object is hit. There's no closer hit
+    /// to look for, so children are visited in the order they're stored in.
+    fn occluded_nodes(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> bool {
+        let (t_min, t_max) = (*t_range.start(), *t_range.end());
+        let mut stack = [0u32; BvhTree::TRAVERSAL_STACK_SIZE];
+        let mut stack_size = 0;
+        let mut id = 0;
+        loop {
+            let node = &self.nodes[id];
+            if node.intersects_ray(ray, t_min, t_max) {
+                if node.object_count > 0 {
+                    let first = node.offset as usize;
+                    let objects = &self.objects[first..first + node.object_count as usize];
+                    if objects
+                        .iter()
+                        .any(|object| object.borrow().occluded(ray, t_range))
+                    {
+                        return true;
+                    }
+                } else {
+                    stack[stack_size] = node.offset;
+                    stack_size += 1;
+                    id += 1;
+                    continue;
+                }
+            }
+            if stack_size == 0 {
+                return false;
+            }
+            stack_size -= 1;
+            id = stack[stack_size] as usize;
+        }
+    }
 
-        let mid = start + (diff as f64 / 2.0) as usize;
+    /// Finds the closest hits of all rays of the `packet` in `t_range`, see `hit`.
+    ///
+    /// Rays are traversed together, so every node is fetched once for the whole packet and its box
+    /// is tested against all rays at once, as are spheres and quads. Other objects are hit one ray
+    /// at a time.
+    pub fn hit_packet(
+        &self,
+        packet: &RayPacket,
+        t_range: &RangeInclusive<f64>,
+    ) -> Vec<Option<HitRecord>> {
+        let t_min = *t_range.start();
+        let mut t_max = [*t_range.end(); PACKET_SIZE];
+        let mut hits: [Option<PacketHit>; PACKET_SIZE] = Default::default();
+        let in_packet: [bool; PACKET_SIZE] = std::array::from_fn(|lane| lane < packet.len());
 
-        let left_children = Self::split_into_nodes(nodes, objects, start, mid);
-        let right_children = Self::split_into_nodes(nodes, objects, mid, end);
+        let mut stack = [0u32; BvhTree::TRAVERSAL_STACK_SIZE];
+        let mut stack_size = 0;
+        let mut id = 0;
+        loop {
+            let node = &self.nodes[id];
+            let active = node.intersects_packet(packet, t_min, &t_max, &in_packet);
+            if let Some(first_active) = active.iter().position(|&active| active) {
+                if node.object_count > 0 {
+                    let first = node.offset as usize;
+                    for index in first..first + node.object_count as usize {
+                        self.hit_object_packet(
+                            index, packet, t_min, &active, &mut t_max, &mut hits,
+                        );
+                    }
+                } else {
+                    // Rays of a packet go in similar directions, so the order suits all of them
+                    let direction = packet.direction(node.axis as usize)[first_active];
+                    let (near, far) = if direction < 0.0 {
+                        (node.offset as usize, id + 1)
+                    } else {
+                        (id + 1, node.offset as usize)
+                    };
+                    stack[stack_size] = far as u32;
+                    stack_size += 1;
+                    id = near;
+                    continue;
+                }
+            }
+            if stack_size == 0 {
+                break;
+            }
+            stack_size -= 1;
+            id = stack[stack_size] as usize;
+        }
 
-        let left_node = Self::new_node(nodes, left_children.0, left_children.1);
-        nodes.push(BvhValue::Node(left_node));
-        let right_node = Self::new_node(nodes, right_children.0, right_children.1);
-        nodes.push(BvhValue::Node(right_node));
-        let len = nodes.len();
-        (len - 2, len - 1)
+        packet
+            .rays()
+            .iter()
+            .zip(hits)
+            .map(|(ray, hit)| match hit? {
+                PacketHit::Record(hit_record) => Some(hit_record),
+                // Hit is already known to be the closest one, so its record is the same
+                // in the whole range
+                PacketHit::Object(index) => self.objects[index].borrow().hit(ray, t_range),
+            })
+            .collect()
     }
 
-    fn new_node(nodes: &[BvhValue], left_id: usize, right_id: usize) -> BvhNode {
-        let bb_left = nodes[left_id].bounding_box();
-        let bb_right = nodes[right_id].bounding_box();
-        BvhNode {
-            left_id,
-            right_id,
-            bounding_box: AxisAlignedBoundingBox::merge(bb_left, bb_right),
+    /// Hits `index`-th object with `active` rays of the `packet`, hits closer than `t_max`
+    /// replace the current ones.
+    fn hit_object_packet(
+        &self,
+        index: usize,
+        packet: &RayPacket,
+        t_min: f64,
+        active: &[bool; PACKET_SIZE],
+        t_max: &mut [f64; PACKET_SIZE],
+        hits: &mut [Option<PacketHit>; PACKET_SIZE],
+    ) {
+        let distances = match self.objects[index].borrow() {
+            HittableObjectType::Sphere(sphere) => sphere.hit_packet(packet, t_min, t_max),
+            HittableObjectType::Quad(quad) => quad.hit_packet(packet, t_min, t_max),
+            object => {
+                for (lane, ray) in packet.rays().iter().enumerate() {
+                    if !active[lane] {
+                        continue;
+                    }
+                    if let Some(hit_record) = object.hit(ray, &(t_min..=t_max[lane])) {
+                        t_max[lane] = hit_record.t();
+                        hits[lane] = Some(PacketHit::Record(hit_record));
+                    }
+                }
+                return;
+            }
+        };
+        for lane in 0..PACKET_SIZE {
+            if let Some(t) = distances[lane].filter(|_| active[lane]) {
+                t_max[lane] = t;
+                hits[lane] = Some(PacketHit::Object(index));
+            }
         }
     }
+
+    /// Returns statistics of the tree, see `BvhStats`.
+    pub fn stats(&self) -> BvhStats {
+        let mut stats = BvhStats {
+            node_count: 0,
+            leaf_count: 0,
+            depth: 0,
+            max_leaf_size: 0,
+            sah_cost: 0.0,
+        };
+        let root_area = self.nodes[0].surface_area();
+        let mut stack = vec![(0, 1)];
+        while let Some((id, depth)) = stack.pop() {
+            let node = &self.nodes[id];
+            let relative_area = node.surface_area() / root_area;
+            stats.node_count += 1;
+            stats.depth = stats.depth.max(depth);
+            if node.object_count > 0 {
+                stats.leaf_count += 1;
+                stats.max_leaf_size = stats.max_leaf_size.max(node.object_count as usize);
+                stats.sah_cost +=
+                    BvhTree::INTERSECTION_COST * node.object_count as f64 * relative_area;
+            } else {
+                stats.sah_cost += BvhTree::TRAVERSAL_COST * relative_area;
+                stack.push((id + 1, depth + 1));
+                stack.push((node.offset as usize, depth + 1));
+            }
+        }
+        stats
+    }
 }
 
-impl HittableObject for BvhTree {
+impl<T: Borrow<HittableObjectType>> HittableObject for BvhTree<T> {
     fn hit(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> Option<HitRecord> {
-        self.hit_node(self.root(), ray, t_range)
+        self.hit_nodes(ray, t_range, &mut 0)
+    }
+
+    fn occluded(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> bool {
+        self.occluded_nodes(ray, t_range)
     }
 
     fn bounding_box(&self) -> &AxisAlignedBoundingBox {
-        match self.root() {
-            BvhValue::Node(bvh_node) => bvh_node.bounding_box(),
-            BvhValue::Leaf(bvh_leaf) => bvh_leaf.bounding_box(),
-        }
+        &self.bounding_box
     }
 }
 
 impl From<Vec<HittableObjectType>> for BvhTree {
     fn from(value: Vec<HittableObjectType>) -> Self {
-        assert!(!value.is_empty());
-        let mut value = if value
-            .iter()
-            .any(|obj| matches!(obj, HittableObjectType::HittableObjectList(_)))
-        {
-            HittableObjectsList::flatten_hittable_objects_list(&value)
-        } else {
-            value
-        };
-        let mut nodes = Vec::new();
-        nodes.push(BvhValue::Node(BvhNode::empty()));
-        let end = value.len();
-        let (left, right) = Self::split_into_nodes(&mut nodes, &mut value, 0, end);
-        let root = Self::new_node(&nodes, left, right);
-        nodes[0] = BvhValue::Node(root);
-        Self { nodes }
+        let objects = value
+            .into_iter()
+            .flat_map(|object| match object {
+                HittableObjectType::HittableObjectList(list) => list
+                    .items_flat()
+                    .iter()
+                    .map(|item| (**item).clone())
+                    .collect(),
+                object => vec![object],
+            })
+            .collect();
+        BvhTree::new(objects, BvhTree::DEFAULT_MAX_LEAF_SIZE)
     }
 }
 
-impl From<HittableObjectsList> for BvhTree {
+/// Items of the list are shared with the tree instead of being cloned.
+impl From<HittableObjectsList> for BvhTree<Arc<HittableObjectType>> {
     fn from(value: HittableObjectsList) -> Self {
-        let items = Vec::from(value.items());
-        BvhTree::from(items)
+        BvhTree::new(value.items_flat(), BvhTree::DEFAULT_MAX_LEAF_SIZE)
+    }
+}
+
+/// Node of the flattened tree, 32 bytes so that two of them fit into a cache line.
+#[derive(Clone, Copy)]
+#[repr(C, align(32))]
+struct LinearNode {
+    /// Corners of the bounding box, rounded outwards to `f32`
+    min: [f32; 3],
+    max: [f32; 3],
+    /// Index of the first object of a leaf, or of the second child of an inner node
+    offset: u32,
+    /// Number of objects of a leaf, 0 for inner nodes
+    object_count: u16,
+    /// Axis on which children of an inner node were split, the first one is on the lower side
+    axis: u8,
+}
+
+const _: () = assert!(std::mem::size_of::<LinearNode>() == 32);
+
+impl LinearNode {
+    fn new(
+        bounding_box: &AxisAlignedBoundingBox,
+        offset: u32,
+        object_count: u16,
+        axis: u8,
+    ) -> Self {
+        let interval = |axis| bounding_box.interval(axis);
+        let (x, y, z) = (interval(Axis::X), interval(Axis::Y), interval(Axis::Z));
+        // Rounding outwards keeps every ray hitting the original box hitting the node too
+        let round_down = |value: f64| {
+            let rounded = value as f32;
+            if rounded as f64 > value {
+                rounded.next_down()
+            } else {
+                rounded
+            }
+        };
+        let round_up = |value: f64| {
+            let rounded = value as f32;
+            if (rounded as f64) < value {
+                rounded.next_up()
+            } else {
+                rounded
+            }
+        };
+        LinearNode {
+            min: [
+                round_down(*x.start()),
+                round_down(*y.start()),
+                round_down(*z.start()),
+            ],
+            max: [round_up(*x.end()), round_up(*y.end()), round_up(*z.end())],
+            offset,
+            object_count,
+            axis,
+        }
+    }
+
+    /// Works the same as `AxisAlignedBoundingBox::intersects_ray`.
+    fn intersects_ray(&self, ray: &Ray, mut t_min: f64, mut t_max: f64) -> bool {
+        for axis in 0..3 {
+            let origin = ray.origin()[axis];
+            let inverse_direction = ray.inverse_direction()[axis];
+            let t0 = (self.min[axis] as f64 - origin) * inverse_direction;
+            let t1 = (self.max[axis] as f64 - origin) * inverse_direction;
+            t_min = t_min.max(t0.min(t1));
+            t_max = t_max.min(t0.max(t1));
+            if t_max <= t_min {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Works the same as `intersects_ray` for all rays of the `packet` at once, returns which
+    /// of the `active` rays hit the node.
+    fn intersects_packet(
+        &self,
+        packet: &RayPacket,
+        t_min: f64,
+        t_max: &[f64; PACKET_SIZE],
+        active: &[bool; PACKET_SIZE],
+    ) -> [bool; PACKET_SIZE] {
+        let mut near = [t_min; PACKET_SIZE];
+        let mut far = *t_max;
+        for axis in 0..3 {
+            let (origin, inverse_direction) = (packet.origin(axis), packet.inverse_direction(axis));
+            let (min, max) = (self.min[axis] as f64, self.max[axis] as f64);
+            for lane in 0..PACKET_SIZE {
+                let t0 = (min - origin[lane]) * inverse_direction[lane];
+                let t1 = (max - origin[lane]) * inverse_direction[lane];
+                near[lane] = near[lane].max(t0.min(t1));
+                far[lane] = far[lane].min(t0.max(t1));
+            }
+        }
+        std::array::from_fn(|lane| active[lane] & (near[lane] < far[lane]))
     }
+
+    fn surface_area(&self) -> f64 {
+        let length = |axis: usize| self.max[axis] as f64 - self.min[axis] as f64;
+        let (x, y, z) = (length(0), length(1), length(2));
+        2.0 * (x * y + y * z + z * x)
+    }
+}
+
+/// Closest hit of a ray of a packet found so far
+enum PacketHit {
+    Record(HitRecord),
+    /// Index of the object hit by the whole packet at once, without computing the record
+    Object(usize),
 }
 
-pub enum BvhValue {
-    Node(BvhNode),
-    Leaf(BvhLeaf),
+/// Node of the tree while it's being built, subtrees are built independently
+/// and flattened into `LinearNode`s afterwards.
+enum BuildNode {
+    Node {
+        left: Box<BuildNode>,
+        right: Box<BuildNode>,
+        axis: Axis,
+        bounding_box: AxisAlignedBoundingBox,
+    },
+    /// Holds `count` objects of the tree starting at `first`
+    Leaf {
+        first: usize,
+        count: usize,
+        bounding_box: AxisAlignedBoundingBox,
+    },
 }
 
-impl BvhValue {
+impl BuildNode {
     fn bounding_box(&self) -> &AxisAlignedBoundingBox {
         match self {
-            BvhValue::Node(node) => node.bounding_box(),
-            BvhValue::Leaf(leaf) => leaf.bounding_box(),
+            BuildNode::Node { bounding_box, .. } | BuildNode::Leaf { bounding_box, .. } => {
+                bounding_box
+            }
         }
     }
 }
 
-/// For efficency reasons we don't allow "null pointers" in our tree, so there sometimes might be duplicated
-/// elements in it - in our case it's fine.
-pub struct BvhNode {
-    left_id: usize,
-    right_id: usize,
+/// Object being sorted into the tree, only its bounds are needed for that
+struct Primitive {
+    /// Index of the object in the original order
+    index: usize,
+    bounding_box: AxisAlignedBoundingBox,
+    centroid: Point3<f64>,
+}
+
+/// Bounding box of primitives and of their centroids
+struct Bounds {
     bounding_box: AxisAlignedBoundingBox,
+    centroid_min: Point3<f64>,
+    centroid_max: Point3<f64>,
 }
 
-impl BvhNode {
-    fn empty() -> Self {
-        BvhNode {
-            left_id: usize::MAX,
-            right_id: usize::MAX,
-            bounding_box: AxisAlignedBoundingBox::empty(),
+impl Bounds {
+    /// Panics if `primitives` are empty.
+    fn of(primitives: &[Primitive]) -> Self {
+        let mut bounds = Bounds {
+            bounding_box: primitives[0].bounding_box.clone(),
+            centroid_min: primitives[0].centroid,
+            centroid_max: primitives[0].centroid,
+        };
+        for primitive in &primitives[1..] {
+            bounds.bounding_box =
+                AxisAlignedBoundingBox::merge(&bounds.bounding_box, &primitive.bounding_box);
+            bounds.centroid_min = bounds.centroid_min.inf(&primitive.centroid);
+            bounds.centroid_max = bounds.centroid_max.sup(&primitive.centroid);
         }
+        bounds
     }
 
-    fn bounding_box(&self) -> &AxisAlignedBoundingBox {
-        &self.bounding_box
+    fn longest_centroid_axis(&self) -> Axis {
+        let extent = self.centroid_max - self.centroid_min;
+        if extent.x >= extent.y && extent.x >= extent.z {
+            Axis::X
+        } else if extent.y >= extent.z {
+            Axis::Y
+        } else {
+            Axis::Z
+        }
+    }
+
+    fn merge(self, other: Bounds) -> Self {
+        Bounds {
+            bounding_box: AxisAlignedBoundingBox::merge(&self.bounding_box, &other.bounding_box),
+            centroid_min: self.centroid_min.inf(&other.centroid_min),
+            centroid_max: self.centroid_max.sup(&other.centroid_max),
+        }
     }
 }
 
-pub struct BvhLeaf {
-    object: HittableObjectType,
+/// Split of primitives between the bins, `bin` is the first one on the right side.
+struct SahSplit {
+    axis: Axis,
+    bin: usize,
+    /// Position and length of the range of centroids on the `axis`, which bins divide evenly
+    min: f64,
+    extent: f64,
+    cost: f64,
 }
 
-impl HittableObject for BvhLeaf {
-    fn hit(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> Option<HitRecord> {
-        self.object.hit(ray, t_range)
+/// Primitives whose centroids fall into a bin
+#[derive(Default)]
+struct Bin {
+    count: usize,
+    bounding_box: Option<AxisAlignedBoundingBox>,
+}
+
+impl Bin {
+    fn add(&mut self, bounding_box: &AxisAlignedBoundingBox) {
+        self.count += 1;
+        self.expand(bounding_box);
     }
 
-    fn bounding_box(&self) -> &AxisAlignedBoundingBox {
-        self.object.bounding_box()
+    fn merge(&mut self, other: &Bin) {
+        if let Some(bounding_box) = &other.bounding_box {
+            self.count += other.count;
+            self.expand(bounding_box);
+        }
+    }
+
+    fn expand(&mut self, bounding_box: &AxisAlignedBoundingBox) {
+        self.bounding_box = Some(match &self.bounding_box {
+            Some(current) => AxisAlignedBoundingBox::merge(current, bounding_box),
+            None => bounding_box.clone(),
+        });
+    }
+
+    fn surface_area(&self) -> f64 {
+        self.bounding_box
+            .as_ref()
+            .map_or(0.0, AxisAlignedBoundingBox::surface_area)
+    }
+}
+
+/// Shape of the built tree, logged to see how well it fits the scene.
+pub struct BvhStats {
+    /// Number of all nodes, including leaves
+    pub node_count: usize,
+    pub leaf_count: usize,
+    /// Number of nodes on the longest path from the root to a leaf
+    pub depth: usize,
+    /// Number of objects in the largest leaf
+    pub max_leaf_size: usize,
+    /// Expected cost of hitting the tree with a random ray, relative to hitting a single object
+    pub sah_cost: f64,
+}
+
+impl fmt::Display for BvhStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} nodes, {} leaves with up to {} objects, depth {}, SAH cost {:.2}",
+            self.node_count, self.leaf_count, self.max_leaf_size, self.depth, self.sah_cost
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+    use crate::object::{quad::Quad, sphere::Sphere};
+
+    use super::*;
+
+    fn random_point(rng: &mut SmallRng, extent: f64) -> Point3<f64> {
+        Point3::new(
+            rng.random_range(-extent..extent),
+            rng.random_range(-extent..extent),
+            rng.random_range(-extent..extent),
+        )
+    }
+
+    fn random_vector(rng: &mut SmallRng, length: f64) -> Vector3<f64> {
+        random_point(rng, length).coords
+    }
+
+    /// Returns `count` random spheres and quads, half of each, scattered in a cube of side 20.
+    fn random_objects(rng: &mut SmallRng, count: usize) -> Vec<HittableObjectType> {
+        (0..count)
+            .map(|index| {
+                let start = random_point(rng, 10.0);
+                if index % 2 == 0 {
+                    Sphere::new(start, rng.random_range(0.05..1.0), 0).into()
+                } else {
+                    Quad::new(start, random_vector(rng, 1.0), random_vector(rng, 1.0), 0).into()
+                }
+            })
+            .collect()
+    }
+
+    fn sphere_centers(objects: &[HittableObjectType]) -> Vec<Point3<f64>> {
+        objects
+            .iter()
+            .filter_map(|object| match object {
+                HittableObjectType::Sphere(sphere) => Some(*sphere.center()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn packet_hits_match_scalar_ones() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let objects = random_objects(&mut rng, 200);
+        let centers = sphere_centers(&objects);
+        let bvh = BvhTree::from(objects);
+        let range = 0.001..=f64::MAX;
+
+        for packet_index in 0..500 {
+            // Every other packet starts inside a sphere, which is hit at its far root
+            let inside = packet_index % 2 == 0;
+            let origin = if inside {
+                centers[rng.random_range(..centers.len())]
+            } else {
+                random_point(&mut rng, 15.0)
+            };
+            let rays: Vec<Ray> = (0..PACKET_SIZE)
+                .map(|_| Ray::new(origin, random_vector(&mut rng, 1.0), 0.0))
+                .collect();
+            let packet = RayPacket::new(&rays);
+            let packet_hits = bvh.hit_packet(&packet, &range);
+            for (ray, packet_hit) in rays.iter().zip(&packet_hits) {
+                let hit = bvh.hit(ray, &range);
+                assert!(!inside || hit.is_some());
+                assert_eq!(
+                    hit.map(|hit| hit.t()),
+                    packet_hit.as_ref().map(|hit| hit.t())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn hits_and_occlusion_match_linear_scan() {
+        let mut rng = SmallRng::seed_from_u64(2);
+        // Largest scene is built in parallel
+        let counts = [1, 9, 300, BvhTree::PARALLEL_BUILD_THRESHOLD + 500];
+        for (count, max_leaf_size) in counts.into_iter().zip([1, 4, 2, 16]) {
+            let objects = random_objects(&mut rng, count);
+            let bvh = BvhTree::new(objects.clone(), max_leaf_size);
+
+            for ray_index in 0..300 {
+                let ray = Ray::new(
+                    random_point(&mut rng, 15.0),
+                    random_vector(&mut rng, 1.0),
+                    0.0,
+                );
+                // Shadow rays end before the light, so some rays have a finite range
+                let t_max = if ray_index % 2 == 0 {
+                    f64::MAX
+                } else {
+                    rng.random_range(1.0..20.0)
+                };
+                let range = 0.001..=t_max;
+
+                let closest = objects
+                    .iter()
+                    .filter_map(|object| object.hit(&ray, &range))
+                    .map(|hit| hit.t())
+                    .min_by(f64::total_cmp);
+                let occluded = objects.iter().any(|object| object.occluded(&ray, &range));
+                assert_eq!(bvh.hit(&ray, &range).map(|hit| hit.t()), closest);
+                assert_eq!(bvh.occluded(&ray, &range), occluded);
+                assert_eq!(occluded, closest.is_some());
+            }
+        }
     }
 }
@@ -5,21 +5,26 @@ use std::{
 
 use indicatif::{ProgressBar, ProgressStyle};
 use nalgebra::{Point3, Unit, UnitVector3, Vector2, Vector3};
-use rand::Rng;
 use rayon::prelude::*;
 use rgb::Rgb;
 use typed_builder::TypedBuilder;
 
 use crate::{
-    core::rendered_image::{Dimensions, RenderedImage},
-    extensions::{
-        rgb_f64_to_u8::RgbF64ToU8Extension, rgb_linear_to_gamma::RgbLinearToGammaExtension,
+    core::{
+        aov::{Aov, AovSample},
+        film::{Film, PixelSamples, Tile},
+        filter::Filter,
+        rendered_image::{Dimensions, RenderedImage},
     },
-    material::Material,
-    object::hittable_object::HittableObject,
-    ray::Ray,
+    extensions::rgb_luminance::RgbLuminanceExtension,
+    integrator::{CameraRay, Integrator, IntegratorType, CAMERA_RAY_RANGE},
+    ray::{
+        ray_packet::{RayPacket, PACKET_SIZE},
+        Ray,
+    },
+    sampler::{self, SampleContext, SamplerType},
     scene::{SceneContent, SceneOptions},
-    utils::random_vector_generator,
+    utils::{random_vector_generator, rng},
 };
 
 #[derive(TypedBuilder)]
@@ -44,8 +49,6 @@ pub struct Camera {
     #[builder(default = 100, setter(into))]
     samples_per_pixel: u32,
     #[builder(default, setter(skip))]
-    pixel_samples_scale: f64,
-    #[builder(default, setter(skip))]
     upper_left_pixel_pos: Point3<f64>,
     #[builder(default, setter(skip))]
     pixel_delta_horizontal: Vector3<f64>,
@@ -65,6 +68,9 @@ pub struct Camera {
     focus_distance: f64,
     #[builder(default, setter(skip))]
     defocus_disk: DefocusDisk,
+    /// Seed of the random numbers used for sampling, the same seed always renders the same image
+    #[builder(default = rng::DEFAULT_SEED, setter(into))]
+    seed: u32,
 }
 
 impl Camera {
@@ -73,88 +79,189 @@ impl Camera {
         scene_content: &SceneContent,
         scene_options: &SceneOptions,
     ) -> RenderedImage {
-        let (tx, rx) = channel::<()>();
-
-        let progress_handler = self.spawn_progress_thread(rx);
-
-        let pixels: Vec<Rgb<u8>> = (0..self.dimensions.height)
-            .into_par_iter()
-            .flat_map(|j| {
-                (0..self.dimensions.width)
-                    .into_par_iter()
-                    .map(|i| {
-                        let color = (0..self.samples_per_pixel)
-                            .map(|_| {
-                                let ray = self.create_ray_around_pixel(i, j);
-                                self.calculate_color(&ray, scene_content, scene_options, 0)
-                            })
-                            .fold(Rgb::new(0.0, 0.0, 0.0), |acc, color| acc + color);
-                        tx.send(()).unwrap();
-                        (color * self.pixel_samples_scale)
-                            .linear_to_gamma()
-                            .f64_to_u8()
-                    })
-                    .collect::<Vec<Rgb<u8>>>()
-            })
-            .collect();
-
-        progress_handler.join().unwrap();
-
-        RenderedImage::new(pixels, self.dimensions).unwrap()
+        self.render_with(
+            scene_content,
+            scene_options,
+            &RenderSettings::default(),
+            None,
+            |_| {},
+        )
+        .to_image()
     }
 
-    fn calculate_color(
+    /// Renders image tile by tile, `on_pass` is called with the accumulated samples after every pass.
+    ///
+    /// In progressive mode, each pass doubles number of samples per pixel (1, 2, 4, ...), otherwise
+    /// all samples are rendered in a single pass. With adaptive sampling, the first pass renders
+    /// minimum number of samples and then number of samples doubles as well, skipping pixels
+    /// which have already converged. Passes are additionally limited to `max_pass_samples` samples.
+    /// Samples are seeded by their index, so without adaptive sampling the final image
+    /// doesn't depend on the number of passes (up to floating point rounding, when the filter
+    /// splats samples into the neighbouring pixels).
+    ///
+    /// If `resume_from` is set, rendering continues from its samples instead of starting
+    /// from the scratch.
+    pub fn render_with<F>(
         &self,
-        ray: &Ray,
         scene_content: &SceneContent,
         scene_options: &SceneOptions,
-        depth: u32,
-    ) -> Rgb<f64> {
-        if depth >= self.max_bounce_depth {
-            return Rgb {
-                r: 0.0,
-                g: 0.0,
-                b: 0.0,
-            };
-        }
+        settings: &RenderSettings,
+        resume_from: Option<Film>,
+        mut on_pass: F,
+    ) -> Film
+    where
+        F: FnMut(&Film),
+    {
+        let mut film = resume_from
+            .unwrap_or_else(|| Film::new(self.dimensions, settings.filter, settings.aovs.clone()));
+        assert_eq!(film.dimensions().width, self.dimensions.width);
+        assert_eq!(film.dimensions().height, self.dimensions.height);
+        assert_eq!(film.filter(), settings.filter);
+        assert_eq!(film.aovs(), settings.aovs);
+        let tiles = self.dimensions.tiles(settings.tile_size);
+
+        let first_pass_samples = settings
+            .adaptive_sampling
+            .map_or(1, |adaptive| adaptive.min_samples_per_pixel);
+
+        let (tx, rx) = channel::<u64>();
+
+        let mut rendered_samples = film.min_sample_count().min(self.samples_per_pixel);
+        let progress_handler = self.spawn_progress_thread(rx, rendered_samples);
+
+        while rendered_samples < self.samples_per_pixel {
+            if !film
+                .pixels()
+                .iter()
+                .any(|pixel| self.is_active(pixel, settings))
+            {
+                break;
+            }
 
-        // We start our range at 0.001 to fix the potential rounding issue, where
-        // ray would reflect in such a way that it would hit the same sphere once again.
-        let full_range = 0.001..=f64::MAX;
-        let hit_record = scene_content.bvh().hit(ray, &full_range);
-        if let Some(hit_record) = hit_record {
-            let material = scene_content
-                .material_by_id(hit_record.material_id())
-                .unwrap();
-            let emitted_color = material.emitted(hit_record.u(), hit_record.v(), hit_record.pos());
-            match material.scatter(ray, &hit_record) {
-                Some(material_scattering) => {
-                    let next_color = self.calculate_color(
-                        material_scattering.scattered_ray(),
+            let pass_samples = if settings.progressive || settings.adaptive_sampling.is_some() {
+                rendered_samples.max(first_pass_samples)
+            } else {
+                self.samples_per_pixel
+            }
+            .min(settings.max_pass_samples.unwrap_or(u32::MAX))
+            .min(self.samples_per_pixel - rendered_samples);
+            rendered_samples += pass_samples;
+
+            let tile_samples: Vec<TileSamples> = tiles
+                .par_iter()
+                .map(|tile| {
+                    let (samples, added_samples) = self.render_tile(
+                        tile,
+                        rendered_samples,
+                        &film,
                         scene_content,
                         scene_options,
-                        depth + 1,
+                        settings,
                     );
-                    let scattered_color: Rgb<f64> = next_color
-                        .iter()
-                        .zip(material_scattering.attenuation().iter())
-                        .map(|(x, y)| x * y)
-                        .collect();
-                    return scattered_color + emitted_color;
-                }
-                None => return emitted_color,
+                    tx.send(added_samples).unwrap();
+                    samples
+                })
+                .collect();
+
+            for (tile, samples) in tiles.iter().zip(&tile_samples) {
+                film.update_tile(tile, &samples.pixels, &samples.aov_sums);
+            }
+            // Samples splatted across tile borders are added in a fixed order, so the image
+            // doesn't depend on the number of threads
+            for (tile, samples) in tiles.iter().zip(&tile_samples) {
+                film.add_splats(tile, &samples.area, &samples.splats);
             }
+
+            on_pass(&film);
         }
 
-        // Ray hit nothing - just return background color
-        *scene_options.background()
+        drop(tx);
+        progress_handler.join().unwrap();
+
+        film
     }
 
-    /// Create ray originating in a defocus disk and directed and random pixel around
-    /// viewport pixel (i, j), with random time in range [0, 1.0]
-    fn create_ray_around_pixel(&self, pixel_x: u32, pixel_y: u32) -> Ray {
-        let offset = Self::sample_square();
+    /// Returns new `Camera` with the same parameters, except the ones set in `overrides`.
+    pub fn with_overrides(&self, overrides: &CameraOverrides) -> Camera {
+        Camera::builder()
+            .width(overrides.width.unwrap_or(self.width))
+            .aspect_ratio(overrides.aspect_ratio.unwrap_or(self.aspect_ratio))
+            .samples_per_pixel(
+                overrides
+                    .samples_per_pixel
+                    .unwrap_or(self.samples_per_pixel),
+            )
+            .max_bounce_depth(overrides.max_bounce_depth.unwrap_or(self.max_bounce_depth))
+            .vertical_fov_angles(
+                overrides
+                    .vertical_fov_angles
+                    .unwrap_or(self.vertical_fov_angles),
+            )
+            .center(overrides.center.unwrap_or(self.center))
+            .look_at(overrides.look_at.unwrap_or(self.look_at))
+            .relative_up(self.relative_up)
+            .defocus_angle(overrides.defocus_angle.unwrap_or(self.defocus_angle))
+            .focus_distance(overrides.focus_distance.unwrap_or(self.focus_distance))
+            .seed(overrides.seed.unwrap_or(self.seed))
+            .build()
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn dimensions(&self) -> Dimensions {
+        self.dimensions
+    }
+
+    pub fn aspect_ratio(&self) -> f64 {
+        self.aspect_ratio
+    }
+
+    pub fn samples_per_pixel(&self) -> u32 {
+        self.samples_per_pixel
+    }
+
+    pub fn max_bounce_depth(&self) -> u32 {
+        self.max_bounce_depth
+    }
+
+    pub fn vertical_fov_angles(&self) -> f64 {
+        self.vertical_fov_angles
+    }
+
+    pub fn center(&self) -> &Point3<f64> {
+        &self.center
+    }
 
+    pub fn look_at(&self) -> &Point3<f64> {
+        &self.look_at
+    }
+
+    pub fn relative_up(&self) -> &Vector3<f64> {
+        &self.relative_up
+    }
+
+    pub fn defocus_angle(&self) -> f64 {
+        self.defocus_angle
+    }
+
+    pub fn focus_distance(&self) -> f64 {
+        self.focus_distance
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    /// Create ray originating in a defocus disk and directed at point `offset` away from the center
+    /// of viewport pixel (i, j), with random time in range [0, 1.0]
+    pub fn create_ray_around_pixel(
+        &self,
+        pixel_x: u32,
+        pixel_y: u32,
+        offset: &Vector2<f64>,
+    ) -> Ray {
         let pixel = self.upper_left_pixel_pos
             + (pixel_x as f64 + offset.x) * self.pixel_delta_horizontal
             + (pixel_y as f64 + offset.y) * self.pixel_delta_vertical;
@@ -167,29 +274,164 @@ impl Camera {
 
         let ray_direction = pixel - ray_origin;
 
-        let mut rng = rand::rng();
-        let ray_time = rng.random();
+        let ray_time = sampler::get_1d();
 
         Ray::new(ray_origin, ray_direction, ray_time)
     }
 
     fn sample_square() -> Vector2<f64> {
-        let mut rng = rand::rng();
-        let f1: f64 = rng.random();
-        let f2: f64 = rng.random();
-        Vector2::new(f1 - 0.5, f2 - 0.5)
+        sampler::get_2d() - Vector2::new(0.5, 0.5)
     }
 
     fn defocus_disk_sample(&self) -> Point3<f64> {
-        let p = random_vector_generator::random_unit_vector2_in_disk();
+        let p = random_vector_generator::point_in_disk(&sampler::get_2d());
         self.center
             + p.x * self.defocus_disk.horizontal_radius
             + p.y * self.defocus_disk.vertical_radius
     }
 
-    fn spawn_progress_thread(&self, rx: Receiver<()>) -> JoinHandle<()> {
-        let all_elements = self.dimensions.all_elements();
-        let pb = ProgressBar::new(all_elements as _);
+    /// Pixel is active if it needs more samples.
+    fn is_active(&self, pixel: &PixelSamples, settings: &RenderSettings) -> bool {
+        pixel.count < self.samples_per_pixel
+            && !settings
+                .adaptive_sampling
+                .is_some_and(|adaptive| adaptive.is_converged(pixel))
+    }
+
+    /// Returns `film` pixels in `tile` (in the order of `Tile::pixels`), with the active ones sampled
+    /// until they have `target_samples` samples, and number of added samples.
+    fn render_tile(
+        &self,
+        tile: &Tile,
+        target_samples: u32,
+        film: &Film,
+        scene_content: &SceneContent,
+        scene_options: &SceneOptions,
+        settings: &RenderSettings,
+    ) -> (TileSamples, u64) {
+        let mut added_samples = 0;
+        let area = tile.expand(settings.filter.pixel_radius(), self.dimensions);
+        let mut samples = TileSamples {
+            pixels: tile.pixels().map(|(i, j)| *film.pixel(i, j)).collect(),
+            aov_sums: tile
+                .pixels()
+                .flat_map(|(i, j)| film.pixel_aov_sums(i, j).to_vec())
+                .collect(),
+            area,
+            splats: vec![PixelSamples::default(); area.all_elements() as usize],
+        };
+        let mut pending = Vec::with_capacity(PACKET_SIZE);
+        for (index, (i, j)) in tile.pixels().enumerate() {
+            if !self.is_active(&samples.pixels[index], settings) {
+                continue;
+            }
+            let pixel_index = film.index(i, j) as u64;
+            for sample in samples.pixels[index].count..target_samples {
+                rng::reseed(rng::sample_seed(
+                    self.seed.into(),
+                    pixel_index,
+                    sample as u64,
+                ));
+                sampler::start_sample(
+                    settings.sampler,
+                    SampleContext {
+                        seed: self.seed.into(),
+                        pixel_x: i,
+                        pixel_y: j,
+                        sample,
+                        samples_per_pixel: self.samples_per_pixel,
+                    },
+                );
+                let offset = Self::sample_square();
+                let ray = self.create_ray_around_pixel(i, j, &offset);
+                pending.push(PendingSample {
+                    index,
+                    pixel: (i, j),
+                    offset,
+                    ray,
+                    rng_state: rng::save(),
+                    sample_state: sampler::save(),
+                });
+                if pending.len() == PACKET_SIZE {
+                    self.finish_samples(
+                        &mut pending,
+                        tile,
+                        &mut samples,
+                        scene_content,
+                        scene_options,
+                        settings,
+                    );
+                }
+                added_samples += 1;
+            }
+        }
+        if !pending.is_empty() {
+            self.finish_samples(
+                &mut pending,
+                tile,
+                &mut samples,
+                scene_content,
+                scene_options,
+                settings,
+            );
+        }
+        (samples, added_samples)
+    }
+
+    /// Traces camera rays of the `pending` samples as a packet and continues the samples
+    /// from their first hits, in the order in which they were started.
+    fn finish_samples(
+        &self,
+        pending: &mut Vec<PendingSample>,
+        tile: &Tile,
+        samples: &mut TileSamples,
+        scene_content: &SceneContent,
+        scene_options: &SceneOptions,
+        settings: &RenderSettings,
+    ) {
+        let rays: Vec<Ray> = pending.iter().map(|sample| sample.ray).collect();
+        let hits = scene_content
+            .bvh()
+            .hit_packet(&RayPacket::new(&rays), &CAMERA_RAY_RANGE);
+        let aov_count = settings.aovs.len();
+        for (sample, hit) in pending.drain(..).zip(hits) {
+            rng::restore(sample.rng_state);
+            sampler::restore(sample.sample_state);
+            let camera_ray = CameraRay::new(sample.ray, hit);
+            let index = sample.index;
+            let color = if settings.aovs.is_empty() {
+                settings.integrator.radiance(
+                    &camera_ray,
+                    scene_content,
+                    scene_options,
+                    self.max_bounce_depth,
+                )
+            } else {
+                let mut aov_sample = AovSample::default();
+                let color = settings.integrator.radiance_with_aovs(
+                    &camera_ray,
+                    scene_content,
+                    scene_options,
+                    self.max_bounce_depth,
+                    &mut aov_sample,
+                );
+                let pixel_aov_sums =
+                    &mut samples.aov_sums[index * aov_count..(index + 1) * aov_count];
+                for (sum, aov) in pixel_aov_sums.iter_mut().zip(&settings.aovs) {
+                    *sum += aov_sample.get(*aov);
+                }
+                color
+            };
+            samples.pixels[index].add(color);
+            samples.splat(tile, &settings.filter, sample.pixel, &sample.offset, color);
+        }
+    }
+
+    /// Progress is measured in samples, `rx` receives number of rendered samples until all senders are dropped.
+    fn spawn_progress_thread(&self, rx: Receiver<u64>, rendered_samples: u32) -> JoinHandle<()> {
+        let all_elements = self.dimensions.all_elements() as u64;
+        let pb = ProgressBar::new(all_elements * self.samples_per_pixel as u64);
+        pb.set_position(all_elements * rendered_samples as u64);
         pb.set_style(
             ProgressStyle::with_template("[{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len}")
                 .unwrap()
@@ -197,11 +439,8 @@ impl Camera {
         );
 
         thread::spawn(move || {
-            let mut counter = 0;
-            for _ in 0..all_elements {
-                rx.recv().unwrap();
-                counter += 1;
-                pb.set_position(counter);
+            for rendered_samples in rx {
+                pb.inc(rendered_samples);
             }
             pb.finish_with_message("rendered");
         })
@@ -227,6 +466,7 @@ impl<
         __aspect_ratio: typed_builder::Optional<f64>,
         __defocus_angle: typed_builder::Optional<f64>,
         __focus_distance: typed_builder::Optional<f64>,
+        __seed: typed_builder::Optional<u32>,
     >
     CameraBuilder<(
         __width,
@@ -239,6 +479,7 @@ impl<
         __aspect_ratio,
         __defocus_angle,
         __focus_distance,
+        __seed,
     )>
 {
     pub fn build(self) -> Camera {
@@ -273,8 +514,6 @@ impl<
         camera.upper_left_pixel_pos = viewport_upper_left
             + 0.5 * (camera.pixel_delta_horizontal + camera.pixel_delta_vertical);
 
-        camera.pixel_samples_scale = 1.0 / camera.samples_per_pixel as f64;
-
         // Calculate defocus disk
         let defocus_radius =
             camera.focus_distance * (camera.defocus_angle / 2.0).to_radians().tan();
@@ -287,6 +526,191 @@ impl<
     }
 }
 
+/// Sample waiting for the rest of the packet its camera ray is traced in.
+struct PendingSample {
+    /// Index of the pixel in the tile
+    index: usize,
+    pixel: (u32, u32),
+    offset: Vector2<f64>,
+    ray: Ray,
+    /// States of the random values, the sample continues from them after its ray is traced
+    rng_state: rng::RngState,
+    sample_state: sampler::SampleState,
+}
+
+/// Samples of the tile pixels, in the order of `Tile::pixels`.
+struct TileSamples {
+    pixels: Vec<PixelSamples>,
+    /// Sums of AOVs values, `RenderSettings::aovs` values per pixel
+    aov_sums: Vec<Rgb<f64>>,
+    /// Tile enlarged by the reach of the filter
+    area: Tile,
+    /// Samples splatted into `area` pixels outside of the tile, in the order of `Tile::pixels`
+    splats: Vec<PixelSamples>,
+}
+
+impl TileSamples {
+    /// Splats `color` of the sample at `offset` from the center of `tile` pixel `(x, y)`
+    /// into the pixels within the reach of the `filter`.
+    fn splat(
+        &mut self,
+        tile: &Tile,
+        filter: &Filter,
+        (x, y): (u32, u32),
+        offset: &Vector2<f64>,
+        color: Rgb<f64>,
+    ) {
+        let radius = filter.pixel_radius() as i64;
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let (target_x, target_y) = (x as i64 + dx, y as i64 + dy);
+                if target_x < 0 || target_y < 0 {
+                    continue;
+                }
+                let (target_x, target_y) = (target_x as u32, target_y as u32);
+                if !self.area.contains(target_x, target_y) {
+                    continue;
+                }
+                let weight = filter.evaluate(offset.x - dx as f64, offset.y - dy as f64);
+                if weight == 0.0 {
+                    continue;
+                }
+                if tile.contains(target_x, target_y) {
+                    let index = (target_y - tile.y) * tile.width + target_x - tile.x;
+                    self.pixels[index as usize].splat(color, weight);
+                } else {
+                    let index = (target_y - self.area.y) * self.area.width + target_x - self.area.x;
+                    self.splats[index as usize].splat(color, weight);
+                }
+            }
+        }
+    }
+}
+
+/// Parameters of the rendering process, which are not part of the scene.
+#[derive(TypedBuilder)]
+pub struct RenderSettings {
+    /// Size of the square tiles the image is split into
+    #[builder(default = 32)]
+    tile_size: u32,
+    /// Whether to render samples in passes of increasing size
+    #[builder(default = false)]
+    progressive: bool,
+    /// Maximum number of samples per pixel rendered in a single pass
+    #[builder(default)]
+    max_pass_samples: Option<u32>,
+    /// If set, pixels stop being sampled once they converge, `samples_per_pixel` becomes the maximum
+    #[builder(default)]
+    adaptive_sampling: Option<AdaptiveSampling>,
+    /// Algorithm computing color of every sample
+    #[builder(default)]
+    integrator: IntegratorType,
+    /// Filter reconstructing pixels from the samples
+    #[builder(default)]
+    filter: Filter,
+    /// Generator of the random values used by samples
+    #[builder(default)]
+    sampler: SamplerType,
+    /// AOVs rendered alongside the main image
+    #[builder(default)]
+    aovs: Vec<Aov>,
+}
+
+impl RenderSettings {
+    pub fn tile_size(&self) -> u32 {
+        self.tile_size
+    }
+
+    pub fn progressive(&self) -> bool {
+        self.progressive
+    }
+
+    pub fn max_pass_samples(&self) -> Option<u32> {
+        self.max_pass_samples
+    }
+
+    pub fn adaptive_sampling(&self) -> Option<AdaptiveSampling> {
+        self.adaptive_sampling
+    }
+
+    pub fn integrator(&self) -> &IntegratorType {
+        &self.integrator
+    }
+
+    pub fn filter(&self) -> Filter {
+        self.filter
+    }
+
+    pub fn sampler(&self) -> &SamplerType {
+        &self.sampler
+    }
+
+    pub fn aovs(&self) -> &[Aov] {
+        &self.aovs
+    }
+}
+
+/// Pixel is converged when it has at least `min_samples_per_pixel` samples and standard error
+/// of its mean luminance is below `error_threshold` relative to the luminance itself.
+#[derive(Clone, Copy)]
+pub struct AdaptiveSampling {
+    min_samples_per_pixel: u32,
+    error_threshold: f64,
+}
+
+impl AdaptiveSampling {
+    /// Relative error of pixels darker than this is measured against this luminance instead,
+    /// so almost black pixels don't require huge number of samples
+    const MIN_LUMINANCE: f64 = 0.01;
+
+    pub fn new(min_samples_per_pixel: u32, error_threshold: f64) -> Self {
+        // At least 2 samples are needed to estimate the variance
+        assert!(min_samples_per_pixel >= 2);
+        assert!(error_threshold > 0.0);
+        Self {
+            min_samples_per_pixel,
+            error_threshold,
+        }
+    }
+
+    pub fn min_samples_per_pixel(&self) -> u32 {
+        self.min_samples_per_pixel
+    }
+
+    pub fn error_threshold(&self) -> f64 {
+        self.error_threshold
+    }
+
+    pub fn is_converged(&self, pixel: &PixelSamples) -> bool {
+        if pixel.count < self.min_samples_per_pixel {
+            return false;
+        }
+        let luminance = pixel.mean().luminance().max(Self::MIN_LUMINANCE);
+        pixel.standard_error() <= self.error_threshold * luminance
+    }
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings::builder().build()
+    }
+}
+
+/// Camera parameters that should replace the ones chosen by the scene.
+#[derive(Default)]
+pub struct CameraOverrides {
+    pub width: Option<u32>,
+    pub aspect_ratio: Option<f64>,
+    pub samples_per_pixel: Option<u32>,
+    pub max_bounce_depth: Option<u32>,
+    pub vertical_fov_angles: Option<f64>,
+    pub center: Option<Point3<f64>>,
+    pub look_at: Option<Point3<f64>>,
+    pub defocus_angle: Option<f64>,
+    pub focus_distance: Option<f64>,
+    pub seed: Option<u32>,
+}
+
 #[derive(Default)]
 struct DefocusDisk {
     horizontal_radius: Vector3<f64>,
@@ -0,0 +1,428 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter, Read, Take, Write},
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+use rgb::Rgb;
+
+use crate::{
+    integrator::{
+        ambient_occlusion::AmbientOcclusion,
+        debug_view::{DebugProperty, DebugView},
+        path_tracer::PathTracer,
+        IntegratorType,
+    },
+    sampler::SamplerType,
+};
+
+use super::{
+    aov::Aov,
+    film::{Film, PixelSamples},
+    filter::{Filter, FilterKind},
+    rendered_image::Dimensions,
+};
+
+const MAGIC: &[u8; 8] = b"NEONCKPT";
+const VERSION: u32 = 6;
+/// Size of `PixelSamples` in the file, without AOV sums
+const PIXEL_SIZE: u64 = 3 * 8 + 8 + 4 + 3 * 8 + 8;
+/// Size of a single AOV sum in the file
+const AOV_SUM_SIZE: u64 = 3 * 8;
+
+/// State of the unfinished render, which can be resumed later.
+///
+/// File starts with `MAGIC`, `VERSION`, scene hash, seed (`u32`), integrator (see `write_integrator`),
+/// sampler as its index in `SamplerType::ALL` (`u8`), image dimensions, number of AOVs
+/// as `u32` and their indices in `Aov::ALL` as `u8`, filter kind as its index in `FilterKind::ALL`
+/// (`u8`) and radius (`f64`), followed by `PixelSamples` of every pixel (sum as 3 `f64`, sum
+/// of squared luminances as `f64`, count as `u32`, weighted sum as 3 `f64` and sum of weights
+/// as `f64`) and sums of its AOVs (3 `f64` each), all little-endian.
+pub struct Checkpoint {
+    /// Hash of the rendered scene, see `scene_exporter::scene_hash`
+    scene_hash: u64,
+    seed: u32,
+    /// Integrator and sampler of the samples in `film`, which can't be mixed with other ones
+    integrator: IntegratorType,
+    sampler: SamplerType,
+    film: Film,
+}
+
+impl Checkpoint {
+    pub fn scene_hash(&self) -> u64 {
+        self.scene_hash
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub fn integrator(&self) -> &IntegratorType {
+        &self.integrator
+    }
+
+    pub fn sampler(&self) -> &SamplerType {
+        &self.sampler
+    }
+
+    pub fn film(&self) -> &Film {
+        &self.film
+    }
+
+    pub fn into_film(self) -> Film {
+        self.film
+    }
+
+    /// Writes checkpoint of `film` rendered with `integrator` and `sampler` to `path`.
+    ///
+    /// Data is written into a temporary file first and then renamed, so a crash while saving
+    /// never leaves a corrupted checkpoint behind.
+    pub fn save<P: AsRef<Path>>(
+        path: P,
+        scene_hash: u64,
+        seed: u32,
+        integrator: &IntegratorType,
+        sampler: &SamplerType,
+        film: &Film,
+    ) -> Result<()> {
+        let path = path.as_ref();
+        let mut temporary_path = path.as_os_str().to_owned();
+        temporary_path.push(".tmp");
+
+        let file = File::create(&temporary_path)
+            .with_context(|| format!("cannot create checkpoint file '{}'", path.display()))?;
+        let mut writer = BufWriter::new(file);
+
+        let dimensions = film.dimensions();
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&scene_hash.to_le_bytes())?;
+        writer.write_all(&seed.to_le_bytes())?;
+        write_integrator(&mut writer, integrator)?;
+        let sampler_index = SamplerType::ALL
+            .iter()
+            .position(|other| other == sampler)
+            .expect("every sampler is in `SamplerType::ALL`");
+        writer.write_all(&[sampler_index as u8])?;
+        writer.write_all(&dimensions.width.to_le_bytes())?;
+        writer.write_all(&dimensions.height.to_le_bytes())?;
+        writer.write_all(&(film.aovs().len() as u32).to_le_bytes())?;
+        for aov in film.aovs() {
+            writer.write_all(&[*aov as u8])?;
+        }
+        writer.write_all(&[film.filter().kind() as u8])?;
+        writer.write_all(&film.filter().radius().to_le_bytes())?;
+        let aov_count = film.aovs().len();
+        for (index, pixel) in film.pixels().iter().enumerate() {
+            let pixel_aov_sums = &film.aov_sums()[index * aov_count..(index + 1) * aov_count];
+            for value in pixel.sum.iter() {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+            writer.write_all(&pixel.luminance_squared_sum.to_le_bytes())?;
+            writer.write_all(&pixel.count.to_le_bytes())?;
+            for value in pixel.weighted_sum.iter() {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+            writer.write_all(&pixel.weight_sum.to_le_bytes())?;
+            for value in pixel_aov_sums.iter().flat_map(|sum| sum.iter()) {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+        }
+        writer.flush()?;
+        drop(writer);
+
+        fs::rename(&temporary_path, path)
+            .with_context(|| format!("cannot write checkpoint file '{}'", path.display()))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Checkpoint> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .with_context(|| format!("cannot open checkpoint file '{}'", path.display()))?;
+        let length = file
+            .metadata()
+            .with_context(|| format!("cannot open checkpoint file '{}'", path.display()))?
+            .len();
+        Self::read(&mut BufReader::new(file).take(length))
+            .with_context(|| format!("invalid checkpoint file '{}'", path.display()))
+    }
+
+    /// Reads checkpoint from the `reader` limited to the size of the file, so dimensions
+    /// can be checked against the size of the pixel data before it's allocated.
+    fn read<R: Read>(reader: &mut Take<R>) -> Result<Checkpoint> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            bail!("not a checkpoint file");
+        }
+        let version = u32::from_le_bytes(read_bytes(reader)?);
+        if version != VERSION {
+            bail!("unsupported checkpoint version {}", version);
+        }
+
+        let scene_hash = u64::from_le_bytes(read_bytes(reader)?);
+        let seed = u32::from_le_bytes(read_bytes(reader)?);
+        let integrator = read_integrator(reader)?;
+        let [sampler_index] = read_bytes(reader)?;
+        let sampler = SamplerType::ALL
+            .get(sampler_index as usize)
+            .copied()
+            .with_context(|| format!("unknown sampler {}", sampler_index))?;
+        let dimensions = Dimensions {
+            width: u32::from_le_bytes(read_bytes(reader)?),
+            height: u32::from_le_bytes(read_bytes(reader)?),
+        };
+
+        let aov_count = u32::from_le_bytes(read_bytes(reader)?);
+        let aovs = (0..aov_count)
+            .map(|_| {
+                let [index] = read_bytes(reader)?;
+                Aov::ALL
+                    .get(index as usize)
+                    .copied()
+                    .with_context(|| format!("unknown AOV {}", index))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let [kind_index] = read_bytes(reader)?;
+        let kind = FilterKind::ALL
+            .get(kind_index as usize)
+            .copied()
+            .with_context(|| format!("unknown filter {}", kind_index))?;
+        let radius = f64::from_le_bytes(read_bytes(reader)?);
+        if radius.is_nan() || radius <= 0.0 {
+            bail!("invalid filter radius {}", radius);
+        }
+        let filter = Filter::new(kind, radius);
+
+        let pixel_count = dimensions.checked_all_elements().with_context(|| {
+            format!(
+                "image dimensions {}x{} are too large",
+                dimensions.width, dimensions.height
+            )
+        })?;
+        let pixel_data_size =
+            (pixel_count as u64).checked_mul(PIXEL_SIZE + aovs.len() as u64 * AOV_SUM_SIZE);
+        if pixel_data_size != Some(reader.limit()) {
+            bail!(
+                "size of pixel data doesn't match image dimensions {}x{}",
+                dimensions.width,
+                dimensions.height
+            );
+        }
+
+        let mut pixels = Vec::with_capacity(pixel_count as usize);
+        let mut aov_sums = Vec::with_capacity(pixels.capacity() * aovs.len());
+        for _ in 0..pixel_count {
+            pixels.push(PixelSamples {
+                sum: read_rgb(reader)?,
+                luminance_squared_sum: f64::from_le_bytes(read_bytes(reader)?),
+                count: u32::from_le_bytes(read_bytes(reader)?),
+                weighted_sum: read_rgb(reader)?,
+                weight_sum: f64::from_le_bytes(read_bytes(reader)?),
+            });
+            for _ in 0..aovs.len() {
+                aov_sums.push(read_rgb(reader)?);
+            }
+        }
+
+        Ok(Checkpoint {
+            scene_hash,
+            seed,
+            integrator,
+            sampler,
+            film: Film::from_pixels(dimensions, filter, pixels, aovs, aov_sums)?,
+        })
+    }
+}
+
+/// Writes kind of the `integrator` (`u8`) followed by its parameters: light sampling (`u8`)
+/// and Russian roulette depth (`u8` flag and `u32` depth) of the path tracer, maximum distance
+/// (`f64`) of ambient occlusion, or property (index in `DebugProperty::ALL` as `u8`) and maximum
+/// BVH cost (`u32`) of the debug view.
+fn write_integrator<W: Write>(writer: &mut W, integrator: &IntegratorType) -> Result<()> {
+    match integrator {
+        IntegratorType::PathTracer(path_tracer) => {
+            let depth = path_tracer.russian_roulette_depth();
+            writer.write_all(&[0, path_tracer.light_sampling() as u8, depth.is_some() as u8])?;
+            writer.write_all(&depth.unwrap_or(0).to_le_bytes())?;
+        }
+        IntegratorType::AmbientOcclusion(ambient_occlusion) => {
+            writer.write_all(&[1])?;
+            writer.write_all(&ambient_occlusion.max_distance().to_le_bytes())?;
+        }
+        IntegratorType::DebugView(debug_view) => {
+            let property_index = DebugProperty::ALL
+                .iter()
+                .position(|property| *property == debug_view.property())
+                .expect("every property is in `DebugProperty::ALL`");
+            writer.write_all(&[2, property_index as u8])?;
+            writer.write_all(&debug_view.max_bvh_cost().to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+fn read_integrator<R: Read>(reader: &mut R) -> Result<IntegratorType> {
+    let [kind] = read_bytes(reader)?;
+    let integrator = match kind {
+        0 => {
+            let [light_sampling, has_depth] = read_bytes(reader)?;
+            let depth = u32::from_le_bytes(read_bytes(reader)?);
+            PathTracer::builder()
+                .light_sampling(light_sampling != 0)
+                .russian_roulette_depth((has_depth != 0).then_some(depth))
+                .build()
+                .into()
+        }
+        1 => {
+            let max_distance = f64::from_le_bytes(read_bytes(reader)?);
+            if max_distance.is_nan() || max_distance <= 0.0 {
+                bail!("invalid ambient occlusion distance {}", max_distance);
+            }
+            AmbientOcclusion::new(max_distance).into()
+        }
+        2 => {
+            let [property_index] = read_bytes(reader)?;
+            let property = DebugProperty::ALL
+                .get(property_index as usize)
+                .copied()
+                .with_context(|| format!("unknown debug property {}", property_index))?;
+            let max_bvh_cost = u32::from_le_bytes(read_bytes(reader)?);
+            if max_bvh_cost == 0 {
+                bail!("invalid maximum BVH cost {}", max_bvh_cost);
+            }
+            DebugView::new(property, max_bvh_cost).into()
+        }
+        _ => bail!("unknown integrator {}", kind),
+    };
+    Ok(integrator)
+}
+
+fn read_rgb<R: Read>(reader: &mut R) -> Result<Rgb<f64>> {
+    Ok(Rgb::new(
+        f64::from_le_bytes(read_bytes(reader)?),
+        f64::from_le_bytes(read_bytes(reader)?),
+        f64::from_le_bytes(read_bytes(reader)?),
+    ))
+}
+
+fn read_bytes<R: Read, const N: usize>(reader: &mut R) -> Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader
+        .read_exact(&mut bytes)
+        .context("unexpected end of file")?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::sampler::sobol::SobolSampler;
+
+    use super::*;
+
+    /// Path in the temporary directory, unique for the test process and `name`.
+    fn temporary_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("neon_{}_{}.ckpt", std::process::id(), name))
+    }
+
+    fn test_film() -> Film {
+        let dimensions = Dimensions {
+            width: 3,
+            height: 2,
+        };
+        let pixels = (0..dimensions.all_elements())
+            .map(|i| {
+                let i = i as f64;
+                PixelSamples {
+                    sum: Rgb::new(i, 2.0 * i, 0.5),
+                    luminance_squared_sum: i * i,
+                    count: 4 + i as u32,
+                    weighted_sum: Rgb::new(0.25, i, 3.0 * i),
+                    weight_sum: 1.5 + i,
+                }
+            })
+            .collect();
+        let aovs = vec![Aov::Depth, Aov::Normal];
+        let aov_sums = (0..dimensions.all_elements() * 2)
+            .map(|i| Rgb::new(i as f64, 1.0, -(i as f64)))
+            .collect();
+        let filter = Filter::new(FilterKind::Tent, 1.25);
+        Film::from_pixels(dimensions, filter, pixels, aovs, aov_sums).unwrap()
+    }
+
+    fn pixel_sums(film: &Film) -> [f64; 4] {
+        film.pixels().iter().fold([0.0; 4], |sums, pixel| {
+            [
+                sums[0] + pixel.sum.r + pixel.sum.g + pixel.sum.b,
+                sums[1] + pixel.luminance_squared_sum + pixel.count as f64,
+                sums[2] + pixel.weighted_sum.r + pixel.weighted_sum.g + pixel.weighted_sum.b,
+                sums[3] + pixel.weight_sum,
+            ]
+        })
+    }
+
+    #[test]
+    fn saved_checkpoint_is_loaded_back_and_corrupted_ones_are_rejected() {
+        let path = temporary_path("saved");
+        let film = test_film();
+        let integrator = AmbientOcclusion::new(2.5).into();
+        let sampler = SobolSampler.into();
+        Checkpoint::save(&path, 42, 7, &integrator, &sampler, &film).unwrap();
+
+        let loaded = Checkpoint::load(&path).unwrap();
+        assert_eq!(loaded.scene_hash(), 42);
+        assert_eq!(loaded.seed(), 7);
+        assert!(*loaded.integrator() == integrator);
+        assert!(*loaded.sampler() == sampler);
+        assert!(loaded.film().filter() == film.filter());
+        assert_eq!(loaded.film().aovs(), film.aovs());
+        assert_eq!(pixel_sums(loaded.film()), pixel_sums(&film));
+        assert_eq!(loaded.film().aov_sums(), film.aov_sums());
+
+        let bytes = fs::read(&path).unwrap();
+        let corrupted_path = temporary_path("corrupted");
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] ^= 0xff;
+        fs::write(&corrupted_path, &bad_magic).unwrap();
+        let error = Checkpoint::load(&corrupted_path).err().unwrap();
+        assert!(format!("{error:#}").contains("not a checkpoint file"));
+
+        for length in [bytes.len() - 1, MAGIC.len() + 6, 0] {
+            fs::write(&corrupted_path, &bytes[..length]).unwrap();
+            assert!(Checkpoint::load(&corrupted_path).is_err());
+        }
+
+        // Dimensions follow magic, version, scene hash, seed, ambient occlusion integrator
+        // (kind and distance) and sampler
+        let dimensions_offset = MAGIC.len() + 4 + 8 + 4 + (1 + 8) + 1;
+        for (width, height, message) in [
+            (
+                60_000u32,
+                60_000u32,
+                "doesn't match image dimensions 60000x60000",
+            ),
+            (
+                100_000,
+                100_000,
+                "image dimensions 100000x100000 are too large",
+            ),
+        ] {
+            let mut oversized = bytes.clone();
+            oversized[dimensions_offset..dimensions_offset + 4]
+                .copy_from_slice(&width.to_le_bytes());
+            oversized[dimensions_offset + 4..dimensions_offset + 8]
+                .copy_from_slice(&height.to_le_bytes());
+            fs::write(&corrupted_path, &oversized).unwrap();
+            let error = Checkpoint::load(&corrupted_path).err().unwrap();
+            assert!(format!("{error:#}").contains(message), "{error:#}");
+        }
+
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&corrupted_path).unwrap();
+    }
+}
@@ -0,0 +1,165 @@
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use rgb::Rgb;
+use typed_builder::TypedBuilder;
+
+use super::{
+    aov::Aov,
+    rendered_image::{Dimensions, RenderedImage},
+};
+
+/// Edge-avoiding À-trous wavelet filter (Dammertz et al. 2010), guided by albedo and normal
+/// of the first hits.
+///
+/// Every iteration blurs the image with 5x5 B3 spline kernel with holes between samples
+/// doubling each time, so the filter reaches `2^(iterations + 1)` pixels far. Neighbours
+/// with different color, normal or albedo get lower weights, which keeps edges sharp.
+/// Illumination (color divided by albedo) is filtered instead of the color, so textures
+/// aren't blurred.
+#[derive(Clone, Copy, TypedBuilder)]
+pub struct Denoiser {
+    #[builder(default = 3)]
+    iterations: u32,
+    /// Sensitivity to illumination differences, halved every iteration
+    #[builder(default = 2.0)]
+    color_sigma: f64,
+    /// Sensitivity to normal differences
+    #[builder(default = 0.1)]
+    normal_sigma: f64,
+    /// Sensitivity to albedo differences
+    #[builder(default = 0.1)]
+    albedo_sigma: f64,
+}
+
+impl Denoiser {
+    /// Albedo channels below this aren't used for demodulation, as dividing by them would
+    /// amplify the noise
+    const MIN_ALBEDO: f64 = 0.01;
+    const KERNEL: [f64; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+
+    pub fn color_sigma(&self) -> f64 {
+        self.color_sigma
+    }
+
+    pub fn normal_sigma(&self) -> f64 {
+        self.normal_sigma
+    }
+
+    pub fn albedo_sigma(&self) -> f64 {
+        self.albedo_sigma
+    }
+
+    /// Returns denoised copy of the `image`, which must have `Aov::Albedo` and `Aov::Normal`.
+    /// AOVs are copied unchanged.
+    pub fn denoise(&self, image: &RenderedImage) -> Result<RenderedImage> {
+        let albedo = image
+            .aov(Aov::Albedo)
+            .context("denoising requires albedo AOV")?;
+        let normal = image
+            .aov(Aov::Normal)
+            .context("denoising requires normal AOV")?;
+
+        let demodulation: Vec<Rgb<f64>> = albedo
+            .iter()
+            .map(|albedo| {
+                albedo
+                    .iter()
+                    .map(|c| if c < Self::MIN_ALBEDO { 1.0 } else { c })
+                    .collect()
+            })
+            .collect();
+        let mut illumination: Vec<Rgb<f64>> = image
+            .pixels()
+            .iter()
+            .zip(&demodulation)
+            .map(|(color, demodulation)| divide(color, demodulation))
+            .collect();
+
+        for iteration in 0..self.iterations {
+            illumination =
+                self.filter(&illumination, albedo, normal, image.dimensions(), iteration);
+        }
+
+        let pixels = illumination
+            .iter()
+            .zip(&demodulation)
+            .map(|(illumination, demodulation)| multiply(illumination, demodulation))
+            .collect();
+        let mut denoised = RenderedImage::new(pixels, image.dimensions())?;
+        for aov in image.aovs() {
+            denoised.add_aov(aov, image.aov(aov).unwrap().to_vec())?;
+        }
+        Ok(denoised)
+    }
+
+    /// Single iteration of the filter, samples are `2^iteration` pixels apart.
+    fn filter(
+        &self,
+        illumination: &[Rgb<f64>],
+        albedo: &[Rgb<f64>],
+        normal: &[Rgb<f64>],
+        dimensions: Dimensions,
+        iteration: u32,
+    ) -> Vec<Rgb<f64>> {
+        let step = 1 << iteration;
+        let color_sigma = self.color_sigma / (1 << iteration) as f64;
+        let (width, height) = (dimensions.width as i64, dimensions.height as i64);
+
+        (0..illumination.len())
+            .into_par_iter()
+            .map(|index| {
+                let (x, y) = (index as i64 % width, index as i64 / width);
+                let mut sum = Rgb::new(0.0, 0.0, 0.0);
+                let mut weight_sum = 0.0;
+                for (j, kernel_y) in Self::KERNEL.iter().enumerate() {
+                    for (i, kernel_x) in Self::KERNEL.iter().enumerate() {
+                        let sample_x = x + (i as i64 - 2) * step;
+                        let sample_y = y + (j as i64 - 2) * step;
+                        if !(0..width).contains(&sample_x) || !(0..height).contains(&sample_y) {
+                            continue;
+                        }
+                        let sample = (sample_y * width + sample_x) as usize;
+
+                        let weight = kernel_x
+                            * kernel_y
+                            * edge_stopping(
+                                &illumination[index],
+                                &illumination[sample],
+                                color_sigma,
+                            )
+                            * edge_stopping(&normal[index], &normal[sample], self.normal_sigma)
+                            * edge_stopping(&albedo[index], &albedo[sample], self.albedo_sigma);
+                        sum += illumination[sample] * weight;
+                        weight_sum += weight;
+                    }
+                }
+                // Center pixel always has positive weight
+                sum / weight_sum
+            })
+            .collect()
+    }
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Denoiser::builder().build()
+    }
+}
+
+/// Weight of the neighbour with feature `b` of the pixel with feature `a`, 1 for the same features.
+fn edge_stopping(a: &Rgb<f64>, b: &Rgb<f64>, sigma: f64) -> f64 {
+    let distance_squared: f64 = a.iter().zip(b.iter()).map(|(a, b)| (a - b).powi(2)).sum();
+    (-distance_squared / (sigma * sigma)).exp()
+}
+
+fn multiply(a: &Rgb<f64>, b: &Rgb<f64>) -> Rgb<f64> {
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).collect()
+}
+
+fn divide(a: &Rgb<f64>, b: &Rgb<f64>) -> Rgb<f64> {
+    a.iter().zip(b.iter()).map(|(x, y)| x / y).collect()
+}
@@ -0,0 +1,296 @@
+use anyhow::{bail, Result};
+use rgb::Rgb;
+
+use crate::{core::aov::Aov, extensions::rgb_luminance::RgbLuminanceExtension, utils::color_map};
+
+use super::{
+    filter::Filter,
+    rendered_image::{Dimensions, RenderedImage},
+};
+
+/// Rectangular part of the image rendered as a single unit of work.
+#[derive(Clone, Copy)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Tile {
+    pub fn all_elements(&self) -> u32 {
+        self.width * self.height
+    }
+
+    /// Returns coordinates of all pixels of the tile, row by row.
+    pub fn pixels(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        (self.y..self.y + self.height)
+            .flat_map(move |j| (self.x..self.x + self.width).map(move |i| (i, j)))
+    }
+
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        (self.x..self.x + self.width).contains(&x) && (self.y..self.y + self.height).contains(&y)
+    }
+
+    /// Returns the tile enlarged by `margin` pixels on every side, clipped to `dimensions`.
+    pub fn expand(&self, margin: u32, dimensions: Dimensions) -> Tile {
+        let x = self.x.saturating_sub(margin);
+        let y = self.y.saturating_sub(margin);
+        Tile {
+            x,
+            y,
+            width: (self.x + self.width + margin).min(dimensions.width) - x,
+            height: (self.y + self.height + margin).min(dimensions.height) - y,
+        }
+    }
+}
+
+impl Dimensions {
+    /// Splits image into tiles of `tile_size`x`tile_size` pixels, tiles at the right
+    /// and bottom edges may be smaller.
+    pub fn tiles(&self, tile_size: u32) -> Vec<Tile> {
+        assert!(tile_size > 0);
+        (0..self.height)
+            .step_by(tile_size as _)
+            .flat_map(|y| {
+                (0..self.width).step_by(tile_size as _).map(move |x| Tile {
+                    x,
+                    y,
+                    width: tile_size.min(self.width - x),
+                    height: tile_size.min(self.height - y),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Accumulated samples of a single pixel.
+///
+/// `sum`, `luminance_squared_sum` and `count` describe samples taken inside the pixel,
+/// while `weighted_sum` and `weight_sum` accumulate samples splatted into it by the `Filter`,
+/// which may come from the neighbouring pixels as well.
+#[derive(Clone, Copy)]
+pub struct PixelSamples {
+    /// Sum of the linear radiance of all samples
+    pub sum: Rgb<f64>,
+    /// Sum of squared luminances of all samples, used for estimating variance
+    pub luminance_squared_sum: f64,
+    pub count: u32,
+    /// Sum of the linear radiance of splatted samples multiplied by their filter weights
+    pub weighted_sum: Rgb<f64>,
+    /// Sum of filter weights of splatted samples
+    pub weight_sum: f64,
+}
+
+impl PixelSamples {
+    pub fn add(&mut self, color: Rgb<f64>) {
+        self.sum += color;
+        self.luminance_squared_sum += color.luminance().powi(2);
+        self.count += 1;
+    }
+
+    pub fn splat(&mut self, color: Rgb<f64>, weight: f64) {
+        self.weighted_sum += color * weight;
+        self.weight_sum += weight;
+    }
+
+    /// Returns weighted average of splatted samples, black if there are none.
+    pub fn filtered_mean(&self) -> Rgb<f64> {
+        if self.weight_sum == 0.0 {
+            Rgb::new(0.0, 0.0, 0.0)
+        } else {
+            self.weighted_sum / self.weight_sum
+        }
+    }
+
+    /// Returns average of all samples, black if there are none.
+    pub fn mean(&self) -> Rgb<f64> {
+        if self.count == 0 {
+            Rgb::new(0.0, 0.0, 0.0)
+        } else {
+            self.sum / self.count as f64
+        }
+    }
+
+    /// Returns standard error of the mean luminance, estimated from the sample variance.
+    pub fn standard_error(&self) -> f64 {
+        if self.count < 2 {
+            return f64::INFINITY;
+        }
+        let n = self.count as f64;
+        let mean = self.sum.luminance() / n;
+        let variance = (self.luminance_squared_sum / n - mean * mean).max(0.0) * n / (n - 1.0);
+        (variance / n).sqrt()
+    }
+}
+
+impl Default for PixelSamples {
+    fn default() -> Self {
+        Self {
+            sum: Rgb::new(0.0, 0.0, 0.0),
+            luminance_squared_sum: 0.0,
+            count: 0,
+            weighted_sum: Rgb::new(0.0, 0.0, 0.0),
+            weight_sum: 0.0,
+        }
+    }
+}
+
+/// Accumulation buffer of the render, storing samples of every pixel.
+pub struct Film {
+    dimensions: Dimensions,
+    /// Filter the samples are splatted with
+    filter: Filter,
+    /// Samples of all pixels, row by row
+    pixels: Vec<PixelSamples>,
+    /// AOVs accumulated alongside the main image
+    aovs: Vec<Aov>,
+    /// Sums of `aovs` values of all samples, `aovs.len()` values per pixel, row by row
+    aov_sums: Vec<Rgb<f64>>,
+}
+
+impl Film {
+    pub fn new(dimensions: Dimensions, filter: Filter, aovs: Vec<Aov>) -> Self {
+        let all_elements = dimensions.all_elements() as usize;
+        Film {
+            dimensions,
+            filter,
+            pixels: vec![PixelSamples::default(); all_elements],
+            aov_sums: vec![Rgb::new(0.0, 0.0, 0.0); all_elements * aovs.len()],
+            aovs,
+        }
+    }
+
+    /// Creates film from previously accumulated `pixels` and `aov_sums`.
+    pub fn from_pixels(
+        dimensions: Dimensions,
+        filter: Filter,
+        pixels: Vec<PixelSamples>,
+        aovs: Vec<Aov>,
+        aov_sums: Vec<Rgb<f64>>,
+    ) -> Result<Self> {
+        if pixels.len() != dimensions.all_elements() as usize {
+            bail!("`pixels` len doesn't match dimensions");
+        }
+        if aov_sums.len() != pixels.len() * aovs.len() {
+            bail!("`aov_sums` len doesn't match dimensions and number of AOVs");
+        }
+        Ok(Film {
+            dimensions,
+            filter,
+            pixels,
+            aovs,
+            aov_sums,
+        })
+    }
+
+    pub fn dimensions(&self) -> Dimensions {
+        self.dimensions
+    }
+
+    pub fn filter(&self) -> Filter {
+        self.filter
+    }
+
+    pub fn pixels(&self) -> &[PixelSamples] {
+        &self.pixels
+    }
+
+    pub fn aovs(&self) -> &[Aov] {
+        &self.aovs
+    }
+
+    pub fn aov_sums(&self) -> &[Rgb<f64>] {
+        &self.aov_sums
+    }
+
+    /// Returns the smallest number of samples of any pixel.
+    pub fn min_sample_count(&self) -> u32 {
+        self.pixels
+            .iter()
+            .map(|pixel| pixel.count)
+            .min()
+            .unwrap_or(0)
+    }
+
+    pub fn index(&self, x: u32, y: u32) -> usize {
+        (y * self.dimensions.width + x) as usize
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> &PixelSamples {
+        &self.pixels[self.index(x, y)]
+    }
+
+    /// Returns sums of AOV values of the pixel, in the order of `aovs`.
+    pub fn pixel_aov_sums(&self, x: u32, y: u32) -> &[Rgb<f64>] {
+        let start = self.index(x, y) * self.aovs.len();
+        &self.aov_sums[start..start + self.aovs.len()]
+    }
+
+    /// Replaces samples of `tile` pixels with `pixels` and their AOV sums with `aov_sums`
+    /// (in the order of `Tile::pixels`).
+    pub fn update_tile(&mut self, tile: &Tile, pixels: &[PixelSamples], aov_sums: &[Rgb<f64>]) {
+        let aov_count = self.aovs.len();
+        assert_eq!(pixels.len(), tile.all_elements() as usize);
+        assert_eq!(aov_sums.len(), pixels.len() * aov_count);
+        for (i, ((x, y), pixel)) in tile.pixels().zip(pixels).enumerate() {
+            let index = self.index(x, y);
+            self.pixels[index] = *pixel;
+            self.aov_sums[index * aov_count..(index + 1) * aov_count]
+                .copy_from_slice(&aov_sums[i * aov_count..(i + 1) * aov_count]);
+        }
+    }
+
+    /// Adds samples splatted into pixels of `area` by samples of `tile` (in the order
+    /// of `Tile::pixels`), only `weighted_sum` and `weight_sum` are used. Pixels of `tile`
+    /// itself are skipped, as they're replaced by `update_tile`.
+    pub fn add_splats(&mut self, tile: &Tile, area: &Tile, splats: &[PixelSamples]) {
+        assert_eq!(splats.len(), area.all_elements() as usize);
+        for ((x, y), splat) in area.pixels().zip(splats) {
+            if tile.contains(x, y) {
+                continue;
+            }
+            let index = self.index(x, y);
+            self.pixels[index].weighted_sum += splat.weighted_sum;
+            self.pixels[index].weight_sum += splat.weight_sum;
+        }
+    }
+
+    /// Returns image with the filtered samples of every pixel and the average of AOV values
+    /// of samples taken inside the pixel (AOVs aren't filtered), pixels without samples are black.
+    pub fn to_image(&self) -> RenderedImage {
+        let pixels = self
+            .pixels
+            .iter()
+            .map(PixelSamples::filtered_mean)
+            .collect();
+        let mut image = RenderedImage::new(pixels, self.dimensions).unwrap();
+        for (i, aov) in self.aovs.iter().enumerate() {
+            let aov_pixels = self
+                .pixels
+                .iter()
+                .enumerate()
+                .map(|(index, pixel)| {
+                    if pixel.count == 0 {
+                        Rgb::new(0.0, 0.0, 0.0)
+                    } else {
+                        self.aov_sums[index * self.aovs.len() + i] / pixel.count as f64
+                    }
+                })
+                .collect();
+            image.add_aov(*aov, aov_pixels).unwrap();
+        }
+        image
+    }
+
+    /// Returns image showing number of samples of every pixel, from blue (no samples)
+    /// through green to red (`max_samples` samples).
+    pub fn sample_count_heatmap(&self, max_samples: u32) -> RenderedImage {
+        let pixels = self
+            .pixels
+            .iter()
+            .map(|pixel| color_map::heatmap(pixel.count as f64 / max_samples.max(1) as f64))
+            .collect();
+        RenderedImage::new(pixels, self.dimensions).unwrap()
+    }
+}
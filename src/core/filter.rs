@@ -0,0 +1,128 @@
+use std::f64::consts::PI;
+
+use clap::ValueEnum;
+
+/// Shape of the pixel reconstruction filter.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, ValueEnum)]
+pub enum FilterKind {
+    /// All samples within the radius have the same weight
+    #[default]
+    Box,
+    /// Weight falls linearly to zero at the radius
+    Tent,
+    /// Gaussian with standard deviation of a third of the radius, shifted to reach zero at the radius
+    Gaussian,
+    /// Mitchell-Netravali cubic with `B = C = 1/3`, stretched over the radius
+    Mitchell,
+    /// Sinc windowed by a wider sinc, which reaches zero at the radius
+    Lanczos,
+}
+
+impl FilterKind {
+    /// All kinds, index in this array identifies the kind in checkpoint files.
+    pub const ALL: [FilterKind; 5] = [
+        FilterKind::Box,
+        FilterKind::Tent,
+        FilterKind::Gaussian,
+        FilterKind::Mitchell,
+        FilterKind::Lanczos,
+    ];
+
+    /// Radius (in pixels) used when none is chosen explicitly.
+    pub fn default_radius(&self) -> f64 {
+        match self {
+            FilterKind::Box => 0.5,
+            FilterKind::Tent => 1.0,
+            FilterKind::Gaussian => 1.5,
+            FilterKind::Mitchell => 2.0,
+            FilterKind::Lanczos => 2.0,
+        }
+    }
+}
+
+/// Pixel reconstruction filter, separable in X and Y.
+///
+/// Every sample is splatted into all pixels whose centers are within `radius` (in pixels) from it,
+/// weighted by the filter value at their offset. Pixel value is the weighted average of the samples
+/// splatted into it. Mitchell and Lanczos filters have negative lobes, which sharpen the image.
+/// The default box filter with radius 0.5 averages samples of every pixel separately.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Filter {
+    kind: FilterKind,
+    radius: f64,
+}
+
+impl Filter {
+    pub fn new(kind: FilterKind, radius: f64) -> Self {
+        assert!(radius > 0.0);
+        Self { kind, radius }
+    }
+
+    pub fn kind(&self) -> FilterKind {
+        self.kind
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    /// Returns how many neighbouring pixels in every direction samples of a pixel can reach.
+    /// Samples are in `[-0.5, 0.5)` range around the pixel center.
+    pub fn pixel_radius(&self) -> u32 {
+        (self.radius - 0.5).max(0.0).ceil() as u32
+    }
+
+    /// Returns weight of the sample at offset `(x, y)` from the pixel center.
+    pub fn evaluate(&self, x: f64, y: f64) -> f64 {
+        self.evaluate_1d(x) * self.evaluate_1d(y)
+    }
+
+    fn evaluate_1d(&self, x: f64) -> f64 {
+        let x = x.abs();
+        if x > self.radius {
+            return 0.0;
+        }
+        match self.kind {
+            FilterKind::Box => 1.0,
+            FilterKind::Tent => 1.0 - x / self.radius,
+            FilterKind::Gaussian => {
+                let sigma = self.radius / 3.0;
+                let gaussian = |x: f64| (-x * x / (2.0 * sigma * sigma)).exp();
+                (gaussian(x) - gaussian(self.radius)).max(0.0)
+            }
+            FilterKind::Mitchell => mitchell(2.0 * x / self.radius),
+            FilterKind::Lanczos => sinc(x) * sinc(x / self.radius),
+        }
+    }
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Filter::new(FilterKind::Box, FilterKind::Box.default_radius())
+    }
+}
+
+/// Mitchell-Netravali cubic for `x` in `[0, 2]`.
+fn mitchell(x: f64) -> f64 {
+    const B: f64 = 1.0 / 3.0;
+    const C: f64 = 1.0 / 3.0;
+    let value = if x < 1.0 {
+        (12.0 - 9.0 * B - 6.0 * C) * x.powi(3)
+            + (-18.0 + 12.0 * B + 6.0 * C) * x.powi(2)
+            + (6.0 - 2.0 * B)
+    } else {
+        (-B - 6.0 * C) * x.powi(3)
+            + (6.0 * B + 30.0 * C) * x.powi(2)
+            + (-12.0 * B - 48.0 * C) * x
+            + (8.0 * B + 24.0 * C)
+    };
+    value / 6.0
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-5 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
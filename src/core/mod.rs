@@ -1,4 +1,10 @@
 pub mod aabb;
+pub mod aov;
 pub mod bvh;
 pub mod camera;
+pub mod checkpoint;
+pub mod denoiser;
+pub mod film;
+pub mod filter;
 pub mod rendered_image;
+pub mod tone_mapping;
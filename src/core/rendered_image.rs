@@ -1,9 +1,18 @@
-use std::path::Path;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
 
-use anyhow::{bail, Error, Result};
+use anyhow::{bail, Context, Error, Result};
 use image::ColorType;
 use rgb::Rgb;
 
+use super::{
+    aov::Aov,
+    tone_mapping::{ToneMapping, TransferFunction},
+};
+
 #[derive(Clone, Copy, Default)]
 pub struct Dimensions {
     pub width: u32,
@@ -24,37 +33,196 @@ impl Dimensions {
     pub fn all_elements(&self) -> u32 {
         self.width * self.height
     }
+
+    /// Same as `all_elements`, but returns `None` instead of overflowing, for dimensions
+    /// which weren't validated yet (e.g. read from a file).
+    pub fn checked_all_elements(&self) -> Option<u32> {
+        self.width.checked_mul(self.height)
+    }
+}
+
+/// Output file formats, chosen by the extension of the output path.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OutputFormat {
+    /// OpenEXR (`.exr`), stores linear 32-bit float values
+    OpenExr,
+    /// Radiance RGBE (`.hdr`), stores linear values with shared exponent
+    RadianceHdr,
+    /// Portable float map (`.pfm`), stores linear 32-bit float values
+    Pfm,
+    /// Any 8-bit format supported by `image` crate (PNG, JPEG, ...), values are tone mapped
+    Ldr,
+}
+
+impl OutputFormat {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> OutputFormat {
+        let extension = path
+            .as_ref()
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("exr") => OutputFormat::OpenExr,
+            Some("hdr") => OutputFormat::RadianceHdr,
+            Some("pfm") => OutputFormat::Pfm,
+            _ => OutputFormat::Ldr,
+        }
+    }
 }
 
+/// Rendered image holding linear radiance of every pixel, optionally with extra AOV channels.
+///
+/// HDR formats store the values as they are, 8-bit formats get them converted with `ToneMapping`.
 pub struct RenderedImage {
     /// `pixels` stores all pixels in the image, row by row
-    pixels: Vec<Rgb<u8>>,
+    pixels: Vec<Rgb<f64>>,
     dimensions: Dimensions,
+    /// Values of every AOV for all pixels, row by row
+    aovs: Vec<(Aov, Vec<Rgb<f64>>)>,
 }
 
 impl RenderedImage {
-    pub fn new(pixels: Vec<Rgb<u8>>, dimensions: Dimensions) -> Result<Self> {
+    pub fn new(pixels: Vec<Rgb<f64>>, dimensions: Dimensions) -> Result<Self> {
         if pixels.len() != dimensions.all_elements() as _ {
             bail!("`pixels` len doesn't match dimensions");
         }
-        Ok(Self { dimensions, pixels })
+        Ok(Self {
+            dimensions,
+            pixels,
+            aovs: vec![],
+        })
+    }
+
+    pub fn pixels(&self) -> &[Rgb<f64>] {
+        &self.pixels
+    }
+
+    /// Adds `aov` channel, replacing the previous one.
+    pub fn add_aov(&mut self, aov: Aov, pixels: Vec<Rgb<f64>>) -> Result<()> {
+        if pixels.len() != self.dimensions.all_elements() as _ {
+            bail!("`pixels` len doesn't match dimensions");
+        }
+        self.remove_aov(aov);
+        self.aovs.push((aov, pixels));
+        Ok(())
+    }
+
+    pub fn remove_aov(&mut self, aov: Aov) {
+        self.aovs.retain(|(existing, _)| *existing != aov);
+    }
+
+    pub fn aov(&self, aov: Aov) -> Option<&[Rgb<f64>]> {
+        self.aovs
+            .iter()
+            .find(|(existing, _)| *existing == aov)
+            .map(|(_, pixels)| pixels.as_slice())
     }
 
-    pub fn as_bytes(&self) -> &[u8] {
-        bytemuck::cast_slice(&self.pixels)
+    pub fn aovs(&self) -> impl Iterator<Item = Aov> + '_ {
+        self.aovs.iter().map(|(aov, _)| *aov)
     }
 
-    pub fn save<P>(&self, path: P) -> Result<()>
+    pub fn dimensions(&self) -> Dimensions {
+        self.dimensions
+    }
+
+    /// Returns pixels converted to 8-bit colors with `tone_mapping`.
+    pub fn to_ldr_pixels(&self, tone_mapping: &ToneMapping) -> Vec<Rgb<u8>> {
+        self.pixels
+            .iter()
+            .map(|pixel| tone_mapping.apply(pixel))
+            .collect()
+    }
+
+    /// Returns pixels as 32-bit floats, non-finite values are replaced with zeros.
+    pub fn to_f32_pixels(&self) -> Vec<Rgb<f32>> {
+        self.pixels
+            .iter()
+            .map(|pixel| {
+                pixel
+                    .iter()
+                    .map(|c| if c.is_finite() { c as f32 } else { 0.0 })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Saves image in the format matching extension of the `path`, see `OutputFormat`.
+    /// `tone_mapping` is used only by 8-bit formats.
+    pub fn save<P>(&self, path: P, tone_mapping: &ToneMapping) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        match OutputFormat::from_path(&path) {
+            OutputFormat::OpenExr | OutputFormat::RadianceHdr => {
+                let pixels = self.to_f32_pixels();
+                self.save_buffer(path, bytemuck::cast_slice(&pixels), ColorType::Rgb32F)
+            }
+            OutputFormat::Pfm => self.save_pfm(path),
+            OutputFormat::Ldr => {
+                let pixels = self.to_ldr_pixels(tone_mapping);
+                self.save_buffer(path, bytemuck::cast_slice(&pixels), ColorType::Rgb8)
+            }
+        }
+    }
+
+    /// Saves every AOV into a separate file next to `image_path`, see `Aov::path`.
+    ///
+    /// Light AOVs are tone mapped with `tone_mapping` in 8-bit formats, the other ones are
+    /// clamped without gamma correction. Returns paths of the saved files.
+    pub fn save_aovs<P>(&self, image_path: P, tone_mapping: &ToneMapping) -> Result<Vec<PathBuf>>
     where
         P: AsRef<Path>,
     {
+        let data_tone_mapping = ToneMapping::builder()
+            .transfer(TransferFunction::Gamma(1.0))
+            .build();
+        self.aovs
+            .iter()
+            .map(|(aov, pixels)| {
+                let path = aov.path(&image_path);
+                let image = RenderedImage::new(pixels.clone(), self.dimensions)?;
+                let tone_mapping = if aov.is_light() {
+                    tone_mapping
+                } else {
+                    &data_tone_mapping
+                };
+                image.save(&path, tone_mapping)?;
+                Ok(path)
+            })
+            .collect()
+    }
+
+    fn save_buffer<P: AsRef<Path>>(&self, path: P, bytes: &[u8], color: ColorType) -> Result<()> {
         image::save_buffer(
             path,
-            self.as_bytes(),
+            bytes,
             self.dimensions.width,
             self.dimensions.height,
-            ColorType::Rgb8,
+            color,
         )
         .map_err(Error::from)
     }
+
+    /// PFM stores rows from bottom to top, negative scale in the header marks little-endian values.
+    fn save_pfm<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .with_context(|| format!("cannot create file '{}'", path.display()))?;
+        let mut writer = BufWriter::new(file);
+
+        write!(
+            writer,
+            "PF\n{} {}\n-1.0\n",
+            self.dimensions.width, self.dimensions.height
+        )?;
+        let pixels = self.to_f32_pixels();
+        for row in pixels.chunks(self.dimensions.width as _).rev() {
+            for value in row.iter().flat_map(|pixel| pixel.iter()) {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
 }
@@ -0,0 +1,135 @@
+use clap::ValueEnum;
+use rgb::Rgb;
+use typed_builder::TypedBuilder;
+
+use crate::extensions::rgb_f64_to_u8::RgbF64ToU8Extension;
+
+/// Operator compressing linear radiance into `[0, 1]` range, applied to every channel separately.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, ValueEnum)]
+pub enum ToneMappingOperator {
+    /// Values above 1 are clipped
+    #[default]
+    Clamp,
+    /// `c / (1 + c)`
+    Reinhard,
+    /// Reinhard operator which maps `white_point` (and everything above it) to 1
+    ExtendedReinhard,
+    /// Krzysztof Narkowicz's fit of the ACES filmic curve
+    Aces,
+    /// John Hable's filmic curve from Uncharted 2
+    Uncharted2,
+}
+
+/// Transfer function encoding tone mapped values before quantization to 8 bits.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TransferFunction {
+    /// `c^(1 / gamma)`
+    Gamma(f64),
+    /// Piecewise sRGB curve
+    Srgb,
+}
+
+impl TransferFunction {
+    pub fn encode(&self, c: f64) -> f64 {
+        match *self {
+            TransferFunction::Gamma(gamma) => c.powf(1.0 / gamma),
+            TransferFunction::Srgb => {
+                if c <= 0.0031308 {
+                    12.92 * c
+                } else {
+                    1.055 * c.powf(1.0 / 2.4) - 0.055
+                }
+            }
+        }
+    }
+}
+
+/// Conversion of linear radiance into displayable 8-bit colors.
+///
+/// Pixel value is multiplied by `2^exposure`, mapped with `operator` and encoded with `transfer`.
+/// Defaults (no exposure change, clamping and gamma 2) match the output of the book's renderer.
+#[derive(Clone, Copy, TypedBuilder)]
+pub struct ToneMapping {
+    #[builder(default)]
+    operator: ToneMappingOperator,
+    /// Exposure adjustment in stops
+    #[builder(default = 0.0)]
+    exposure: f64,
+    /// Smallest value mapped to pure white by `ToneMappingOperator::ExtendedReinhard`
+    #[builder(default = 4.0)]
+    white_point: f64,
+    #[builder(default = TransferFunction::Gamma(2.0))]
+    transfer: TransferFunction,
+}
+
+impl ToneMapping {
+    pub fn operator(&self) -> ToneMappingOperator {
+        self.operator
+    }
+
+    pub fn exposure(&self) -> f64 {
+        self.exposure
+    }
+
+    pub fn white_point(&self) -> f64 {
+        self.white_point
+    }
+
+    pub fn transfer(&self) -> TransferFunction {
+        self.transfer
+    }
+
+    pub fn apply(&self, color: &Rgb<f64>) -> Rgb<u8> {
+        let scale = self.exposure.exp2();
+        color
+            .iter()
+            .map(|c| {
+                let mapped = self.map(c.max(0.0) * scale).clamp(0.0, 1.0);
+                self.transfer.encode(mapped)
+            })
+            .collect::<Rgb<f64>>()
+            .f64_to_u8()
+    }
+
+    fn map(&self, c: f64) -> f64 {
+        match self.operator {
+            ToneMappingOperator::Clamp => c,
+            ToneMappingOperator::Reinhard => c / (1.0 + c),
+            ToneMappingOperator::ExtendedReinhard => {
+                let white_squared = self.white_point * self.white_point;
+                c * (1.0 + c / white_squared) / (1.0 + c)
+            }
+            ToneMappingOperator::Aces => (c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14),
+            ToneMappingOperator::Uncharted2 => {
+                const EXPOSURE_BIAS: f64 = 2.0;
+                const LINEAR_WHITE: f64 = 11.2;
+                uncharted2_curve(c * EXPOSURE_BIAS) / uncharted2_curve(LINEAR_WHITE)
+            }
+        }
+    }
+}
+
+impl Default for ToneMapping {
+    fn default() -> Self {
+        ToneMapping::builder().build()
+    }
+}
+
+fn uncharted2_curve(x: f64) -> f64 {
+    const SHOULDER_STRENGTH: f64 = 0.15;
+    const LINEAR_STRENGTH: f64 = 0.5;
+    const LINEAR_ANGLE: f64 = 0.1;
+    const TOE_STRENGTH: f64 = 0.2;
+    const TOE_NUMERATOR: f64 = 0.02;
+    const TOE_DENOMINATOR: f64 = 0.3;
+
+    let (a, b, c, d, e, f) = (
+        SHOULDER_STRENGTH,
+        LINEAR_STRENGTH,
+        LINEAR_ANGLE,
+        TOE_STRENGTH,
+        TOE_NUMERATOR,
+        TOE_DENOMINATOR,
+    );
+    ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f
+}
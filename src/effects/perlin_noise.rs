@@ -1,7 +1,7 @@
 use nalgebra::{Point3, UnitVector3, Vector3};
 use rand::Rng;
 
-use crate::utils::random_vector_generator;
+use crate::utils::{random_vector_generator, rng};
 
 const PERLIN_POINTS_COUNT: usize = 256;
 
@@ -14,18 +14,24 @@ pub struct PerlinNoise {
 }
 
 impl PerlinNoise {
-    pub fn new() -> Self {
+    /// Generates random vectors and permutations from `seed`, so the same seed always gives
+    /// the same noise. State of the thread-local generator is left untouched.
+    pub fn new(seed: u64) -> Self {
+        let state = rng::save();
+        rng::reseed(seed);
         let mut rand_vec =
             [UnitVector3::new_unchecked(Vector3::new(1.0, 0.0, 0.0)); PERLIN_POINTS_COUNT];
         (0..PERLIN_POINTS_COUNT).for_each(|i| {
             rand_vec[i] = random_vector_generator::random_unit_vector3_in_sphere();
         });
-        Self {
+        let noise = Self {
             perm_x: Box::new(Self::generate_perm()),
             perm_y: Box::new(Self::generate_perm()),
             perm_z: Box::new(Self::generate_perm()),
             rand_vec: Box::new(rand_vec),
-        }
+        };
+        rng::restore(state);
+        noise
     }
 
     pub fn noise(&self, pos: &Point3<f64>) -> f64 {
@@ -110,16 +116,10 @@ impl PerlinNoise {
     }
 
     fn permute(arr: &mut [usize; PERLIN_POINTS_COUNT]) {
-        let mut rng = rand::rng();
+        let mut rng = rng::rng();
         for i in (1..=PERLIN_POINTS_COUNT - 1).rev() {
             let to_swap = rng.random::<u32>() as usize % i;
             arr.swap(to_swap, i);
         }
     }
 }
-
-impl Default for PerlinNoise {
-    fn default() -> Self {
-        Self::new()
-    }
-}
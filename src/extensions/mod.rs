@@ -1,5 +1,5 @@
 pub mod rgb_f64_to_u8;
-pub mod rgb_linear_to_gamma;
+pub mod rgb_luminance;
 pub mod ri_move_by_offset;
 pub mod ri_surrounds;
 pub mod vector_reflection;
@@ -0,0 +1,12 @@
+use rgb::Rgb;
+
+pub trait RgbLuminanceExtension {
+    fn luminance(&self) -> f64;
+}
+
+impl RgbLuminanceExtension for Rgb<f64> {
+    /// Relative luminance of linear Rec. 709 color
+    fn luminance(&self) -> f64 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+}
@@ -0,0 +1,52 @@
+use rgb::Rgb;
+
+use crate::{
+    object::hittable_object::HittableObject,
+    ray::ray_generator,
+    scene::{SceneContent, SceneOptions},
+};
+
+use super::{CameraRay, Integrator};
+
+/// Renders fraction of the hemisphere above the first hit which isn't blocked by other objects
+/// closer than `max_distance`, ignoring materials and lights. Rays that hit nothing are black.
+#[derive(Clone, Copy, PartialEq)]
+pub struct AmbientOcclusion {
+    max_distance: f64,
+}
+
+impl AmbientOcclusion {
+    pub fn new(max_distance: f64) -> Self {
+        assert!(max_distance > 0.0);
+        AmbientOcclusion { max_distance }
+    }
+
+    pub fn max_distance(&self) -> f64 {
+        self.max_distance
+    }
+}
+
+impl Integrator for AmbientOcclusion {
+    fn radiance(
+        &self,
+        camera_ray: &CameraRay,
+        scene_content: &SceneContent,
+        _: &SceneOptions,
+        _: u32,
+    ) -> Rgb<f64> {
+        let Some(hit_record) = camera_ray.hit() else {
+            return Rgb::new(0.0, 0.0, 0.0);
+        };
+        // Cosine-weighted directions, so the average visibility is weighted by the cosine as well
+        let occlusion_ray = ray_generator::random_ray_on_hemisphere(camera_ray.ray(), hit_record);
+        let occlusion_range = 0.001..=self.max_distance;
+        if scene_content
+            .bvh()
+            .occluded(&occlusion_ray, &occlusion_range)
+        {
+            Rgb::new(0.0, 0.0, 0.0)
+        } else {
+            Rgb::new(1.0, 1.0, 1.0)
+        }
+    }
+}
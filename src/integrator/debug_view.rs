@@ -0,0 +1,107 @@
+use clap::ValueEnum;
+use rgb::Rgb;
+
+use crate::{
+    material::Material,
+    scene::{SceneContent, SceneOptions},
+    utils::color_map,
+};
+
+use super::{CameraRay, Integrator, CAMERA_RAY_RANGE};
+
+/// Property of the first object hit by the camera ray.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+pub enum DebugProperty {
+    /// Outward normal mapped from `[-1, 1]` to `[0, 1]`
+    Normal,
+    /// Base color of the material
+    Albedo,
+    /// Distance from the camera (linear, use HDR output format or `exposure` to see it)
+    Depth,
+    /// Texture coordinates as red and green channels
+    Uv,
+    /// Distinct color for every material
+    MaterialId,
+    /// Number of BVH nodes visited by the camera ray, from blue (none) to red (`max_bvh_cost`)
+    BvhCost,
+}
+
+impl DebugProperty {
+    /// All properties, index in this array identifies the property in checkpoint files.
+    pub const ALL: [DebugProperty; 6] = [
+        DebugProperty::Normal,
+        DebugProperty::Albedo,
+        DebugProperty::Depth,
+        DebugProperty::Uv,
+        DebugProperty::MaterialId,
+        DebugProperty::BvhCost,
+    ];
+}
+
+/// Renders `property` of the first hit of every camera ray instead of the lighting,
+/// rays that hit nothing are black.
+#[derive(Clone, Copy, PartialEq)]
+pub struct DebugView {
+    property: DebugProperty,
+    /// Number of visited nodes rendered as red by `DebugProperty::BvhCost`
+    max_bvh_cost: u32,
+}
+
+impl DebugView {
+    pub fn new(property: DebugProperty, max_bvh_cost: u32) -> Self {
+        assert!(max_bvh_cost > 0);
+        DebugView {
+            property,
+            max_bvh_cost,
+        }
+    }
+
+    pub fn property(&self) -> DebugProperty {
+        self.property
+    }
+
+    pub fn max_bvh_cost(&self) -> u32 {
+        self.max_bvh_cost
+    }
+}
+
+impl Integrator for DebugView {
+    fn radiance(
+        &self,
+        camera_ray: &CameraRay,
+        scene_content: &SceneContent,
+        _: &SceneOptions,
+        _: u32,
+    ) -> Rgb<f64> {
+        if self.property == DebugProperty::BvhCost {
+            // Cost is measured by tracing the ray on its own, as other rays do
+            let (_, cost) = scene_content
+                .bvh()
+                .hit_with_cost(camera_ray.ray(), &CAMERA_RAY_RANGE);
+            return color_map::heatmap(cost as f64 / self.max_bvh_cost as f64);
+        }
+        let Some(hit_record) = camera_ray.hit() else {
+            return Rgb::new(0.0, 0.0, 0.0);
+        };
+
+        match self.property {
+            DebugProperty::Normal => hit_record
+                .outward_normal()
+                .map(|c| (c + 1.0) / 2.0)
+                .iter()
+                .copied()
+                .collect(),
+            DebugProperty::Albedo => scene_content
+                .material_by_id(hit_record.material_id())
+                .unwrap()
+                .albedo(hit_record),
+            DebugProperty::Depth => {
+                let t = hit_record.t();
+                Rgb::new(t, t, t)
+            }
+            DebugProperty::Uv => Rgb::new(hit_record.u(), hit_record.v(), 0.0),
+            DebugProperty::MaterialId => color_map::id_color(hit_record.material_id()),
+            DebugProperty::BvhCost => unreachable!(),
+        }
+    }
+}
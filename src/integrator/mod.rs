@@ -0,0 +1,168 @@
+pub mod ambient_occlusion;
+pub mod debug_view;
+pub mod path_tracer;
+
+use ambient_occlusion::AmbientOcclusion;
+use debug_view::DebugView;
+use path_tracer::PathTracer;
+use std::ops::RangeInclusive;
+
+use rgb::Rgb;
+
+use crate::{
+    core::aov::{Aov, AovSample},
+    object::hittable_object::{HitRecord, HittableObject},
+    ray::Ray,
+    scene::{SceneContent, SceneOptions},
+};
+
+/// Range of distances in which camera rays hit objects. It starts at 0.001 to fix the potential
+/// rounding issue, where reflected ray would hit the same object once again.
+pub const CAMERA_RAY_RANGE: RangeInclusive<f64> = 0.001..=f64::MAX;
+
+/// Ray sent from the camera together with its closest hit in `CAMERA_RAY_RANGE`, which camera
+/// finds for whole packets of rays at once.
+pub struct CameraRay {
+    ray: Ray,
+    hit: Option<HitRecord>,
+}
+
+impl CameraRay {
+    /// `hit` must be the closest hit of the `ray`, see `trace`.
+    pub fn new(ray: Ray, hit: Option<HitRecord>) -> Self {
+        CameraRay { ray, hit }
+    }
+
+    /// Finds the closest hit of the `ray` on its own.
+    pub fn trace(ray: Ray, scene_content: &SceneContent) -> Self {
+        let hit = scene_content.bvh().hit(&ray, &CAMERA_RAY_RANGE);
+        CameraRay { ray, hit }
+    }
+
+    pub fn ray(&self) -> &Ray {
+        &self.ray
+    }
+
+    pub fn hit(&self) -> Option<&HitRecord> {
+        self.hit.as_ref()
+    }
+}
+
+/// Algorithm computing light arriving at the camera.
+pub trait Integrator {
+    /// Returns light arriving along the `camera_ray`, bouncing at most `max_bounce_depth` times.
+    fn radiance(
+        &self,
+        camera_ray: &CameraRay,
+        scene_content: &SceneContent,
+        scene_options: &SceneOptions,
+        max_bounce_depth: u32,
+    ) -> Rgb<f64>;
+
+    /// Works the same as `radiance`, additionally recording AOVs of the sample into `aovs`.
+    ///
+    /// By default, properties of the first hit are recorded and all the light counts as direct.
+    fn radiance_with_aovs(
+        &self,
+        camera_ray: &CameraRay,
+        scene_content: &SceneContent,
+        scene_options: &SceneOptions,
+        max_bounce_depth: u32,
+        aovs: &mut AovSample,
+    ) -> Rgb<f64> {
+        aovs.record_first_hit(camera_ray, scene_content);
+        let radiance = self.radiance(camera_ray, scene_content, scene_options, max_bounce_depth);
+        aovs.add(Aov::Direct, radiance);
+        radiance
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum IntegratorType {
+    PathTracer(PathTracer),
+    AmbientOcclusion(AmbientOcclusion),
+    DebugView(DebugView),
+}
+
+impl Integrator for IntegratorType {
+    fn radiance(
+        &self,
+        camera_ray: &CameraRay,
+        scene_content: &SceneContent,
+        scene_options: &SceneOptions,
+        max_bounce_depth: u32,
+    ) -> Rgb<f64> {
+        match self {
+            IntegratorType::PathTracer(path_tracer) => {
+                path_tracer.radiance(camera_ray, scene_content, scene_options, max_bounce_depth)
+            }
+            IntegratorType::AmbientOcclusion(ambient_occlusion) => ambient_occlusion.radiance(
+                camera_ray,
+                scene_content,
+                scene_options,
+                max_bounce_depth,
+            ),
+            IntegratorType::DebugView(debug_view) => {
+                debug_view.radiance(camera_ray, scene_content, scene_options, max_bounce_depth)
+            }
+        }
+    }
+
+    fn radiance_with_aovs(
+        &self,
+        camera_ray: &CameraRay,
+        scene_content: &SceneContent,
+        scene_options: &SceneOptions,
+        max_bounce_depth: u32,
+        aovs: &mut AovSample,
+    ) -> Rgb<f64> {
+        match self {
+            IntegratorType::PathTracer(path_tracer) => path_tracer.radiance_with_aovs(
+                camera_ray,
+                scene_content,
+                scene_options,
+                max_bounce_depth,
+                aovs,
+            ),
+            IntegratorType::AmbientOcclusion(ambient_occlusion) => ambient_occlusion
+                .radiance_with_aovs(
+                    camera_ray,
+                    scene_content,
+                    scene_options,
+                    max_bounce_depth,
+                    aovs,
+                ),
+            IntegratorType::DebugView(debug_view) => debug_view.radiance_with_aovs(
+                camera_ray,
+                scene_content,
+                scene_options,
+                max_bounce_depth,
+                aovs,
+            ),
+        }
+    }
+}
+
+impl Default for IntegratorType {
+    fn default() -> Self {
+        PathTracer::default().into()
+    }
+}
+
+impl From<PathTracer> for IntegratorType {
+    fn from(value: PathTracer) -> Self {
+        IntegratorType::PathTracer(value)
+    }
+}
+
+impl From<AmbientOcclusion> for IntegratorType {
+    fn from(value: AmbientOcclusion) -> Self {
+        IntegratorType::AmbientOcclusion(value)
+    }
+}
+
+impl From<DebugView> for IntegratorType {
+    fn from(value: DebugView) -> Self {
+        IntegratorType::DebugView(value)
+    }
+}
@@ -0,0 +1,247 @@
+use rgb::Rgb;
+use typed_builder::TypedBuilder;
+
+use crate::{
+    core::aov::{Aov, AovSample},
+    material::{Material, MaterialType},
+    object::{
+        hittable_object::{HitRecord, HittableObject},
+        sampleable_object::SampleableObject,
+    },
+    ray::Ray,
+    sampler,
+    scene::{SceneContent, SceneOptions},
+};
+
+use super::{CameraRay, Integrator, CAMERA_RAY_RANGE};
+
+/// Distance from both ends of the shadow ray in which hits are ignored, so that it doesn't hit
+/// the surface it starts from or the light itself
+const SHADOW_RAY_EPSILON: f64 = 0.001;
+
+/// Unidirectional path tracer, which samples lights directly at non-specular surfaces
+/// and combines it with BSDF sampling using multiple importance sampling.
+#[derive(Clone, Copy, PartialEq, TypedBuilder)]
+pub struct PathTracer {
+    /// Whether to sample lights directly, otherwise light is found only by BSDF sampling
+    #[builder(default = true)]
+    light_sampling: bool,
+    /// Depth from which paths are terminated with Russian roulette, `None` disables it
+    #[builder(default = Some(3))]
+    russian_roulette_depth: Option<u32>,
+}
+
+impl PathTracer {
+    pub fn light_sampling(&self) -> bool {
+        self.light_sampling
+    }
+
+    pub fn russian_roulette_depth(&self) -> Option<u32> {
+        self.russian_roulette_depth
+    }
+
+    /// Samples direction towards a random light from the surface hit by `ray` and returns
+    /// light scattered by its `material` towards the `ray` origin, weighted against BSDF sampling.
+    fn sample_direct_light(
+        &self,
+        ray: &Ray,
+        hit_record: &HitRecord,
+        material: &MaterialType,
+        scene_content: &SceneContent,
+    ) -> Rgb<f64> {
+        let black = Rgb::new(0.0, 0.0, 0.0);
+        let lights = scene_content.lights();
+        let direction = lights.random_direction(hit_record.pos());
+        let direction_out = -*ray.direction();
+        let light_pdf = lights.pdf_value(hit_record.pos(), &direction);
+        let bsdf_pdf = material.pdf(hit_record, &direction, &direction_out);
+        if light_pdf <= 0.0 || bsdf_pdf <= 0.0 {
+            return black;
+        }
+
+        // Light is found among the lights only, and then the shadow ray checks whether
+        // any object lies between it and the surface
+        let shadow_ray = Ray::new(*hit_record.pos(), direction.into_inner(), ray.time());
+        let Some(light_hit_record) = scene_content
+            .lights()
+            .hit(&shadow_ray, &(SHADOW_RAY_EPSILON..=f64::MAX))
+        else {
+            return black;
+        };
+        let shadow_range = SHADOW_RAY_EPSILON..=light_hit_record.t() - SHADOW_RAY_EPSILON;
+        if scene_content.bvh().occluded(&shadow_ray, &shadow_range) {
+            return black;
+        }
+        let light_material = scene_content
+            .material_by_id(light_hit_record.material_id())
+            .unwrap();
+        let emitted_color = light_material.emitted(
+            light_hit_record.u(),
+            light_hit_record.v(),
+            light_hit_record.pos(),
+        );
+
+        let scattering = material.eval(hit_record, &direction, &direction_out);
+        let scale = power_heuristic(light_pdf, bsdf_pdf) / light_pdf;
+        multiply(&emitted_color, &scattering) * scale
+    }
+
+    /// Traces path starting with the `camera_ray`, recording AOVs into `aovs` if it's set.
+    fn trace(
+        &self,
+        camera_ray: &CameraRay,
+        scene_content: &SceneContent,
+        scene_options: &SceneOptions,
+        max_bounce_depth: u32,
+        mut aovs: Option<&mut AovSample>,
+    ) -> Rgb<f64> {
+        let sample_lights = self.light_sampling && !scene_content.lights().is_empty();
+
+        let mut radiance = Rgb::new(0.0, 0.0, 0.0);
+        // Product of attenuations of all bounces so far, divided by Russian roulette
+        // survival probabilities
+        let mut throughput = Rgb::new(1.0, 1.0, 1.0);
+        let mut ray = *camera_ray.ray();
+        // Density with which the material at the last bounce sampled the ray direction, `None`
+        // if the light at that point wasn't sampled directly (camera ray or specular material).
+        // Light emitted by the hit object is then weighted against direct light sampling.
+        let mut previous_bsdf_pdf = None;
+
+        for depth in 0..max_bounce_depth {
+            let hit_record = if depth == 0 {
+                camera_ray.hit().cloned()
+            } else {
+                scene_content.bvh().hit(&ray, &CAMERA_RAY_RANGE)
+            };
+            let Some(hit_record) = hit_record else {
+                // Ray hit nothing - just add background color
+                let background_color = multiply(&throughput, scene_options.background());
+                if let Some(aovs) = aovs.as_deref_mut() {
+                    aovs.add(light_aov(depth, false), background_color);
+                }
+                radiance += background_color;
+                break;
+            };
+
+            let material = scene_content
+                .material_by_id(hit_record.material_id())
+                .unwrap();
+            let mut emitted_color =
+                material.emitted(hit_record.u(), hit_record.v(), hit_record.pos());
+            if let Some(bsdf_pdf) = previous_bsdf_pdf {
+                let light_pdf = scene_content
+                    .lights()
+                    .pdf_value(ray.origin(), ray.direction());
+                emitted_color *= power_heuristic(bsdf_pdf, light_pdf);
+            }
+            let emitted_color = multiply(&throughput, &emitted_color);
+            if let Some(aovs) = aovs.as_deref_mut() {
+                if depth == 0 {
+                    aovs.record_hit(&hit_record, material);
+                }
+                aovs.add(light_aov(depth, false), emitted_color);
+            }
+            radiance += emitted_color;
+
+            let Some(material_scattering) = material.scatter(&ray, &hit_record) else {
+                break;
+            };
+
+            // Specular directions can't be sampled by lights
+            previous_bsdf_pdf = material_scattering.pdf().filter(|_| sample_lights);
+            if previous_bsdf_pdf.is_some() {
+                let direct_color = multiply(
+                    &throughput,
+                    &self.sample_direct_light(&ray, &hit_record, material, scene_content),
+                );
+                if let Some(aovs) = aovs.as_deref_mut() {
+                    aovs.add(light_aov(depth, true), direct_color);
+                }
+                radiance += direct_color;
+            }
+
+            throughput = multiply(&throughput, material_scattering.attenuation());
+            ray = *material_scattering.scattered_ray();
+
+            // Russian roulette - paths with low throughput are terminated randomly and
+            // the surviving ones are boosted, so the estimate stays unbiased
+            if self
+                .russian_roulette_depth
+                .is_some_and(|min_depth| depth + 1 >= min_depth)
+            {
+                let survival_probability = throughput.iter().fold(0.0, f64::max).min(1.0);
+                if sampler::get_1d() >= survival_probability {
+                    break;
+                }
+                throughput /= survival_probability;
+            }
+        }
+
+        radiance
+    }
+}
+
+impl Default for PathTracer {
+    fn default() -> Self {
+        PathTracer::builder().build()
+    }
+}
+
+impl Integrator for PathTracer {
+    fn radiance(
+        &self,
+        camera_ray: &CameraRay,
+        scene_content: &SceneContent,
+        scene_options: &SceneOptions,
+        max_bounce_depth: u32,
+    ) -> Rgb<f64> {
+        self.trace(
+            camera_ray,
+            scene_content,
+            scene_options,
+            max_bounce_depth,
+            None,
+        )
+    }
+
+    /// Light found at the first hit is split into emission, direct and indirect light.
+    fn radiance_with_aovs(
+        &self,
+        camera_ray: &CameraRay,
+        scene_content: &SceneContent,
+        scene_options: &SceneOptions,
+        max_bounce_depth: u32,
+        aovs: &mut AovSample,
+    ) -> Rgb<f64> {
+        self.trace(
+            camera_ray,
+            scene_content,
+            scene_options,
+            max_bounce_depth,
+            Some(aovs),
+        )
+    }
+}
+
+/// Returns light AOV receiving light found at `depth`, `sampled` is true if the light
+/// was found by direct light sampling.
+fn light_aov(depth: u32, sampled: bool) -> Aov {
+    match (depth, sampled) {
+        (0, false) => Aov::Emission,
+        (0, true) | (1, false) => Aov::Direct,
+        _ => Aov::Indirect,
+    }
+}
+
+/// Power heuristic weight of the sample taken with `pdf` against the other strategy with `other_pdf`.
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let (pdf_squared, other_pdf_squared) = (pdf * pdf, other_pdf * other_pdf);
+    if pdf_squared + other_pdf_squared == 0.0 {
+        return 0.0;
+    }
+    pdf_squared / (pdf_squared + other_pdf_squared)
+}
+
+fn multiply(a: &Rgb<f64>, b: &Rgb<f64>) -> Rgb<f64> {
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).collect()
+}
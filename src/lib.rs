@@ -2,9 +2,11 @@ pub mod cli;
 pub mod core;
 pub mod effects;
 pub mod extensions;
+pub mod integrator;
 pub mod material;
 pub mod object;
 pub mod ray;
+pub mod sampler;
 pub mod scene;
 pub mod texture;
 pub mod utils;
@@ -1,26 +1,174 @@
-use std::env;
+use std::{env, time::Instant};
 
 use anyhow::{bail, Result};
-use log::{error, info};
-use neon::cli;
+use log::{error, info, warn};
+use neon::{
+    cli::{self, Command, SCENE_PRESETS},
+    core::{
+        checkpoint::Checkpoint,
+        tone_mapping::{ToneMapping, TransferFunction},
+    },
+    scene::scene_exporter,
+};
 
 fn main() -> Result<()> {
     env_logger::init();
 
     // Parse args
     let cli_args: Vec<String> = env::args().collect();
-    let args = cli::parse_args(&cli_args)?;
+    let args = match cli::parse_args(&cli_args)? {
+        Command::Render(args) => args,
+        Command::ListScenes => {
+            for preset in SCENE_PRESETS {
+                println!("{:<16} {}", preset.name, preset.description);
+            }
+            return Ok(());
+        }
+    };
+
+    // Scene files are exported instead of being rendered
+    if args.output_path.ends_with(".toml") {
+        scene_exporter::export_scene(&args.scene, &args.output_path)?;
+        info!("Exported scene to {}", args.output_path);
+        return Ok(());
+    }
+
+    // Resume
+    let scene_hash = scene_exporter::scene_hash(&args.scene)?;
+    let seed = args.scene.camera().seed();
+    let resume_from = match &args.checkpoint {
+        Some(checkpoint) if checkpoint.resume => {
+            let loaded = Checkpoint::load(&checkpoint.path)?;
+            if loaded.scene_hash() != scene_hash {
+                bail!(
+                    "checkpoint '{}' was made for a different scene or camera",
+                    checkpoint.path
+                );
+            }
+            if loaded.seed() != seed {
+                bail!(
+                    "checkpoint '{}' was made with a different seed",
+                    checkpoint.path
+                );
+            }
+            if loaded.integrator() != args.render_settings.integrator() {
+                bail!(
+                    "checkpoint '{}' was made with a different integrator or its settings",
+                    checkpoint.path
+                );
+            }
+            if loaded.sampler() != args.render_settings.sampler() {
+                bail!(
+                    "checkpoint '{}' was made with a different sampler",
+                    checkpoint.path
+                );
+            }
+            if loaded.film().filter() != args.render_settings.filter() {
+                bail!(
+                    "checkpoint '{}' was made with a different filter",
+                    checkpoint.path
+                );
+            }
+            if loaded.film().aovs() != args.render_settings.aovs() {
+                bail!(
+                    "checkpoint '{}' was made with different AOVs",
+                    checkpoint.path
+                );
+            }
+            info!(
+                "Resuming from {} with {} samples per pixel",
+                checkpoint.path,
+                loaded.film().min_sample_count()
+            );
+            Some(loaded.into_film())
+        }
+        _ => None,
+    };
 
     // Render
     info!("Starting rendering");
-    let rendered = args.scene.render();
+    let mut last_preview: Option<Instant> = None;
+    let mut last_checkpoint = Instant::now();
+    let film = args
+        .scene
+        .render_with(&args.render_settings, resume_from, |film| {
+            // Failed previews and checkpoints shouldn't stop the render
+            if let Some(preview) = &args.preview {
+                if last_preview.is_none_or(|last| last.elapsed() >= preview.interval) {
+                    let image = match &args.denoiser {
+                        Some(denoiser) => denoiser.denoise(&film.to_image()),
+                        None => Ok(film.to_image()),
+                    };
+                    match image.and_then(|image| image.save(&preview.path, &args.tone_mapping)) {
+                        Ok(()) => info!("Written preview to {}", preview.path),
+                        Err(e) => warn!("Cannot save preview file: {}", e),
+                    }
+                    last_preview = Some(Instant::now());
+                }
+            }
+
+            if let Some(checkpoint) = &args.checkpoint {
+                if last_checkpoint.elapsed() >= checkpoint.interval {
+                    match Checkpoint::save(
+                        &checkpoint.path,
+                        scene_hash,
+                        seed,
+                        args.render_settings.integrator(),
+                        args.render_settings.sampler(),
+                        film,
+                    ) {
+                        Ok(()) => info!("Written checkpoint to {}", checkpoint.path),
+                        Err(e) => warn!("Cannot save checkpoint file: {:#}", e),
+                    }
+                    last_checkpoint = Instant::now();
+                }
+            }
+        });
     info!("Finished rendering");
 
+    // Final checkpoint allows continuing the render with more samples
+    if let Some(checkpoint) = &args.checkpoint {
+        Checkpoint::save(
+            &checkpoint.path,
+            scene_hash,
+            seed,
+            args.render_settings.integrator(),
+            args.render_settings.sampler(),
+            &film,
+        )?;
+        info!("Written checkpoint to {}", checkpoint.path);
+    }
+
+    if let Some(heatmap_path) = &args.heatmap_path {
+        let heatmap = film.sample_count_heatmap(args.scene.camera().samples_per_pixel());
+        // Heatmap colors are already in `[0, 1]` range, so they're written as they are
+        let tone_mapping = ToneMapping::builder()
+            .transfer(TransferFunction::Gamma(1.0))
+            .build();
+        heatmap.save(heatmap_path, &tone_mapping)?;
+        info!("Written sample count heatmap to {}", heatmap_path);
+    }
+
     // Encode
-    if let Err(e) = rendered.save(args.output_path) {
+    let mut image = film.to_image();
+    if let Some(denoiser) = &args.denoiser {
+        image = denoiser.denoise(&image)?;
+        info!("Denoised image");
+    }
+    let unsaved_aovs: Vec<_> = image
+        .aovs()
+        .filter(|aov| !args.saved_aovs.contains(aov))
+        .collect();
+    for aov in unsaved_aovs {
+        image.remove_aov(aov);
+    }
+    if let Err(e) = image.save(&args.output_path, &args.tone_mapping) {
         error!("Cannot save output file: {}", e);
         bail!(e)
     }
+    for path in image.save_aovs(&args.output_path, &args.tone_mapping)? {
+        info!("Written AOV to {}", path.display());
+    }
 
     Ok(())
 }
@@ -19,6 +19,10 @@ impl Dielectric {
     pub fn new(refraction_index: f64) -> Self {
         Dielectric { refraction_index }
     }
+
+    pub fn refraction_index(&self) -> f64 {
+        self.refraction_index
+    }
 }
 
 impl Material for Dielectric {
@@ -36,4 +40,8 @@ impl Material for Dielectric {
         let material_scattering = MaterialScattering::new(attenuation, scattered_ray);
         Some(material_scattering)
     }
+
+    fn albedo(&self, _: &HitRecord) -> Rgb<f64> {
+        Rgb::new(1.0, 1.0, 1.0)
+    }
 }
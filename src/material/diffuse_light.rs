@@ -18,6 +18,10 @@ impl DiffuseLight {
     pub fn new(texture: TextureType) -> Self {
         Self { texture }
     }
+
+    pub fn texture(&self) -> &TextureType {
+        &self.texture
+    }
 }
 
 impl From<Rgb<f64>> for DiffuseLight {
@@ -34,6 +38,12 @@ impl Material for DiffuseLight {
         None
     }
 
+    /// Lights don't reflect anything, their emitted color is used instead
+    fn albedo(&self, hit_record: &HitRecord) -> Rgb<f64> {
+        self.texture
+            .color_at(hit_record.u(), hit_record.v(), hit_record.pos())
+    }
+
     fn emitted(&self, u: f64, v: f64, pos: &Point3<f64>) -> Rgb<f64> {
         self.texture.color_at(u, v, pos)
     }
@@ -1,3 +1,6 @@
+use std::f64::consts::PI;
+
+use nalgebra::UnitVector3;
 use rgb::Rgb;
 
 use crate::{
@@ -17,6 +20,10 @@ impl Isotropic {
     pub fn new(texture: TextureType) -> Self {
         Self { texture }
     }
+
+    pub fn texture(&self) -> &TextureType {
+        &self.texture
+    }
 }
 
 impl From<Rgb<f64>> for Isotropic {
@@ -34,6 +41,27 @@ impl Material for Isotropic {
         let attenuation = self
             .texture
             .color_at(hit_record.u(), hit_record.v(), hit_record.pos());
-        Some(MaterialScattering::new(attenuation, scattered_ray))
+        let pdf = self.pdf(hit_record, scattered_ray.direction(), &-*ray.direction());
+        Some(MaterialScattering::with_pdf(
+            attenuation,
+            scattered_ray,
+            pdf,
+        ))
+    }
+
+    fn albedo(&self, hit_record: &HitRecord) -> Rgb<f64> {
+        self.texture
+            .color_at(hit_record.u(), hit_record.v(), hit_record.pos())
+    }
+
+    /// Phase function is uniform over the whole sphere, there is no cosine term inside the volume.
+    fn eval(&self, hit_record: &HitRecord, _: &UnitVector3<f64>, _: &UnitVector3<f64>) -> Rgb<f64> {
+        self.texture
+            .color_at(hit_record.u(), hit_record.v(), hit_record.pos())
+            / (4.0 * PI)
+    }
+
+    fn pdf(&self, _: &HitRecord, _: &UnitVector3<f64>, _: &UnitVector3<f64>) -> f64 {
+        1.0 / (4.0 * PI)
     }
 }
@@ -1,3 +1,6 @@
+use std::f64::consts::PI;
+
+use nalgebra::UnitVector3;
 use rgb::Rgb;
 
 use crate::{
@@ -17,6 +20,10 @@ impl Lambertian {
     pub fn new(texture: TextureType) -> Self {
         Self { texture }
     }
+
+    pub fn texture(&self) -> &TextureType {
+        &self.texture
+    }
 }
 
 impl From<Rgb<f64>> for Lambertian {
@@ -30,10 +37,38 @@ impl From<Rgb<f64>> for Lambertian {
 impl Material for Lambertian {
     fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<MaterialScattering> {
         let scattered_ray = ray_generator::random_ray_on_hemisphere(ray, hit_record);
+        // BSDF is albedo / PI, so with cosine-weighted sampling attenuation is just the albedo
         let attenuation = self
             .texture
             .color_at(hit_record.u(), hit_record.v(), hit_record.pos());
-        let material_scattering = MaterialScattering::new(attenuation, scattered_ray);
+        let pdf = self.pdf(hit_record, scattered_ray.direction(), &-*ray.direction());
+        let material_scattering = MaterialScattering::with_pdf(attenuation, scattered_ray, pdf);
         Some(material_scattering)
     }
+
+    fn albedo(&self, hit_record: &HitRecord) -> Rgb<f64> {
+        self.texture
+            .color_at(hit_record.u(), hit_record.v(), hit_record.pos())
+    }
+
+    fn eval(
+        &self,
+        hit_record: &HitRecord,
+        direction_in: &UnitVector3<f64>,
+        _: &UnitVector3<f64>,
+    ) -> Rgb<f64> {
+        let cosine = hit_record.normal().dot(direction_in).max(0.0);
+        self.texture
+            .color_at(hit_record.u(), hit_record.v(), hit_record.pos())
+            * (cosine / PI)
+    }
+
+    fn pdf(
+        &self,
+        hit_record: &HitRecord,
+        direction_in: &UnitVector3<f64>,
+        _: &UnitVector3<f64>,
+    ) -> f64 {
+        hit_record.normal().dot(direction_in).max(0.0) / PI
+    }
 }
@@ -21,6 +21,14 @@ impl Metal {
         assert!(fuzziness >= 0.0);
         Metal { albedo, fuzziness }
     }
+
+    pub fn albedo(&self) -> &Rgb<f64> {
+        &self.albedo
+    }
+
+    pub fn fuzziness(&self) -> f64 {
+        self.fuzziness
+    }
 }
 
 impl Material for Metal {
@@ -36,4 +44,8 @@ impl Material for Metal {
             None
         }
     }
+
+    fn albedo(&self, _: &HitRecord) -> Rgb<f64> {
+        self.albedo
+    }
 }
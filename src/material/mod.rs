@@ -9,13 +9,39 @@ use diffuse_light::DiffuseLight;
 use isotropic::Isotropic;
 use lambertian::Lambertian;
 use metal::Metal;
-use nalgebra::Point3;
+use nalgebra::{Point3, UnitVector3};
 use rgb::Rgb;
 
 use crate::{object::hittable_object::HitRecord, ray::Ray};
 
+/// Directions passed to `eval` and `pdf` point away from the surface: `direction_in` towards
+/// the incoming light and `direction_out` towards the viewer (opposite of the hitting ray).
 pub trait Material {
+    /// Samples direction of the light scattered from the `ray` hitting the surface,
+    /// `None` if the ray is absorbed.
     fn scatter(&self, ray: &Ray, hit_record: &HitRecord) -> Option<MaterialScattering>;
+    /// Returns base color of the surface, regardless of the lighting.
+    fn albedo(&self, hit_record: &HitRecord) -> Rgb<f64>;
+    /// Returns BSDF multiplied by the cosine of `direction_in` and the surface normal,
+    /// black for materials which scatter only in specular directions.
+    fn eval(
+        &self,
+        _: &HitRecord,
+        _direction_in: &UnitVector3<f64>,
+        _direction_out: &UnitVector3<f64>,
+    ) -> Rgb<f64> {
+        Rgb::new(0.0, 0.0, 0.0)
+    }
+    /// Returns probability density (with respect to solid angle) of `scatter` sampling
+    /// `direction_in`, 0 for materials which scatter only in specular directions.
+    fn pdf(
+        &self,
+        _: &HitRecord,
+        _direction_in: &UnitVector3<f64>,
+        _direction_out: &UnitVector3<f64>,
+    ) -> f64 {
+        0.0
+    }
     fn emitted(&self, _: f64, _: f64, _: &Point3<f64>) -> Rgb<f64> {
         // By default material emmits no light - just black color
         Rgb::new(0.0, 0.0, 0.0)
@@ -42,6 +68,62 @@ impl Material for MaterialType {
         }
     }
 
+    fn albedo(&self, hit_record: &HitRecord) -> Rgb<f64> {
+        match self {
+            MaterialType::Lambertian(lambertian) => lambertian.albedo(hit_record),
+            MaterialType::Metal(metal) => Material::albedo(metal, hit_record),
+            MaterialType::Dielectric(dielectric) => dielectric.albedo(hit_record),
+            MaterialType::DiffuseLight(diffuse_light) => diffuse_light.albedo(hit_record),
+            MaterialType::Isotropic(isotropic) => isotropic.albedo(hit_record),
+        }
+    }
+
+    fn eval(
+        &self,
+        hit_record: &HitRecord,
+        direction_in: &UnitVector3<f64>,
+        direction_out: &UnitVector3<f64>,
+    ) -> Rgb<f64> {
+        match self {
+            MaterialType::Lambertian(lambertian) => {
+                lambertian.eval(hit_record, direction_in, direction_out)
+            }
+            MaterialType::Metal(metal) => metal.eval(hit_record, direction_in, direction_out),
+            MaterialType::Dielectric(dielectric) => {
+                dielectric.eval(hit_record, direction_in, direction_out)
+            }
+            MaterialType::DiffuseLight(diffuse_light) => {
+                diffuse_light.eval(hit_record, direction_in, direction_out)
+            }
+            MaterialType::Isotropic(isotropic) => {
+                isotropic.eval(hit_record, direction_in, direction_out)
+            }
+        }
+    }
+
+    fn pdf(
+        &self,
+        hit_record: &HitRecord,
+        direction_in: &UnitVector3<f64>,
+        direction_out: &UnitVector3<f64>,
+    ) -> f64 {
+        match self {
+            MaterialType::Lambertian(lambertian) => {
+                lambertian.pdf(hit_record, direction_in, direction_out)
+            }
+            MaterialType::Metal(metal) => metal.pdf(hit_record, direction_in, direction_out),
+            MaterialType::Dielectric(dielectric) => {
+                dielectric.pdf(hit_record, direction_in, direction_out)
+            }
+            MaterialType::DiffuseLight(diffuse_light) => {
+                diffuse_light.pdf(hit_record, direction_in, direction_out)
+            }
+            MaterialType::Isotropic(isotropic) => {
+                isotropic.pdf(hit_record, direction_in, direction_out)
+            }
+        }
+    }
+
     fn emitted(&self, u: f64, v: f64, pos: &Point3<f64>) -> Rgb<f64> {
         match self {
             MaterialType::Lambertian(lambertian) => lambertian.emitted(u, v, pos),
@@ -84,15 +166,29 @@ impl From<Isotropic> for MaterialType {
 }
 
 pub struct MaterialScattering {
+    /// `eval` of the scattered direction divided by its `pdf`
     attenuation: Rgb<f64>,
     scattered_ray: Ray,
+    /// Probability density of the scattered direction, `None` if it's the specular one
+    pdf: Option<f64>,
 }
 
 impl MaterialScattering {
+    /// Creates scattering in the specular direction.
     pub fn new(attenuation: Rgb<f64>, scattered_ray: Ray) -> Self {
         MaterialScattering {
             attenuation,
             scattered_ray,
+            pdf: None,
+        }
+    }
+
+    /// Creates scattering in the direction sampled with probability density `pdf`.
+    pub fn with_pdf(attenuation: Rgb<f64>, scattered_ray: Ray, pdf: f64) -> Self {
+        MaterialScattering {
+            attenuation,
+            scattered_ray,
+            pdf: Some(pdf),
         }
     }
 
@@ -103,4 +199,8 @@ impl MaterialScattering {
     pub fn scattered_ray(&self) -> &Ray {
         &self.scattered_ray
     }
+
+    pub fn pdf(&self) -> Option<f64> {
+        self.pdf
+    }
 }
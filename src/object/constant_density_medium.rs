@@ -3,7 +3,7 @@ use std::ops::RangeInclusive;
 use nalgebra::{Unit, Vector3};
 use rand::Rng;
 
-use crate::{core::aabb::AxisAlignedBoundingBox, ray::Ray};
+use crate::{core::aabb::AxisAlignedBoundingBox, ray::Ray, utils::rng};
 
 use super::{
     hittable_object::{HitRecord, HittableObject},
@@ -31,6 +31,14 @@ impl ConstantDensityMedium {
     pub fn boundary(&self) -> &HittableObjectType {
         &self.boundary
     }
+
+    pub fn density(&self) -> f64 {
+        -1.0 / self.negative_inverse_density
+    }
+
+    pub fn phase_function_id(&self) -> usize {
+        self.phase_function_id
+    }
 }
 
 impl HittableObject for ConstantDensityMedium {
@@ -51,7 +59,7 @@ impl HittableObject for ConstantDensityMedium {
         let ray_length = ray.direction().norm();
         let distance_inside_boundary = (r_max - r_min) * ray_length;
 
-        let mut rng = rand::rng();
+        let mut rng = rng::rng();
         let hit_distance = self.negative_inverse_density * (rng.random::<f64>()).ln();
 
         if hit_distance > distance_inside_boundary {
@@ -75,6 +83,12 @@ impl HittableObject for ConstantDensityMedium {
         Some(hit_record)
     }
 
+    /// Ray scatters inside the medium at a random distance, so it's occluded only if it would
+    /// be scattered by `hit`, which also keeps the random numbers drawn the same.
+    fn occluded(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> bool {
+        self.hit(ray, t_range).is_some()
+    }
+
     fn bounding_box(&self) -> &AxisAlignedBoundingBox {
         self.boundary().bounding_box()
     }
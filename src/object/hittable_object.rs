@@ -6,9 +6,13 @@ use crate::{core::aabb::AxisAlignedBoundingBox, ray::Ray};
 
 pub trait HittableObject {
     fn hit(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> Option<HitRecord>;
+    /// Returns whether the `ray` hits anything in `t_range`. Unlike `hit`, it stops
+    /// at the first hit found and doesn't compute its record, so it's cheaper for shadow rays.
+    fn occluded(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> bool;
     fn bounding_box(&self) -> &AxisAlignedBoundingBox;
 }
 
+#[derive(Clone)]
 pub struct HitRecord {
     pos: Point3<f64>,
     /// This normal vector always points againt the ray
@@ -88,6 +92,15 @@ impl HitRecord {
         &self.normal
     }
 
+    /// Returns normal pointing out of the object, regardless of the ray direction.
+    pub fn outward_normal(&self) -> UnitVector3<f64> {
+        if self.front_face {
+            self.normal
+        } else {
+            -self.normal
+        }
+    }
+
     pub fn t(&self) -> f64 {
         self.t
     }
@@ -1,4 +1,4 @@
-use std::ops::RangeInclusive;
+use std::{ops::RangeInclusive, sync::Arc};
 
 use crate::core::aabb::AxisAlignedBoundingBox;
 
@@ -7,9 +7,10 @@ use super::{
     HittableObjectType,
 };
 
+/// Items are shared, so cloning the list or flattening it into a BVH doesn't copy them.
 #[derive(Clone)]
 pub struct HittableObjectsList {
-    items: Vec<HittableObjectType>,
+    items: Vec<Arc<HittableObjectType>>,
     bounding_box: AxisAlignedBoundingBox,
 }
 
@@ -23,22 +24,24 @@ impl HittableObjectsList {
 
     pub fn add(&mut self, item: HittableObjectType) {
         self.bounding_box = AxisAlignedBoundingBox::merge(&self.bounding_box, item.bounding_box());
-        self.items.push(item);
+        self.items.push(Arc::new(item));
     }
 
-    pub fn items(&self) -> &[HittableObjectType] {
+    pub fn items(&self) -> &[Arc<HittableObjectType>] {
         &self.items
     }
 
     /// Returns flat vector of all elements stored inside `HittableObjectsList`
-    pub fn items_flat(&self) -> Vec<HittableObjectType> {
+    pub fn items_flat(&self) -> Vec<Arc<HittableObjectType>> {
         Self::flatten_hittable_objects_list(self.items())
     }
 
     /// Returns flat vector of all elements stored inside `objs`
-    pub fn flatten_hittable_objects_list(objs: &[HittableObjectType]) -> Vec<HittableObjectType> {
+    pub fn flatten_hittable_objects_list(
+        objs: &[Arc<HittableObjectType>],
+    ) -> Vec<Arc<HittableObjectType>> {
         objs.iter()
-            .flat_map(|item| match item {
+            .flat_map(|item| match &**item {
                 HittableObjectType::HittableObjectList(list) => list.items_flat(),
                 _ => vec![item.clone()],
             })
@@ -63,6 +66,10 @@ impl HittableObject for HittableObjectsList {
         closest_hit
     }
 
+    fn occluded(&self, ray: &crate::ray::Ray, t_range: &RangeInclusive<f64>) -> bool {
+        self.items.iter().any(|item| item.occluded(ray, t_range))
+    }
+
     fn bounding_box(&self) -> &AxisAlignedBoundingBox {
         &self.bounding_box
     }
@@ -76,7 +83,7 @@ impl From<Vec<HittableObjectType>> for HittableObjectsList {
                 AxisAlignedBoundingBox::merge(&acc, ho.bounding_box())
             });
         HittableObjectsList {
-            items: value,
+            items: value.into_iter().map(Arc::new).collect(),
             bounding_box,
         }
     }
@@ -0,0 +1,261 @@
+use std::{borrow::Borrow, ops::RangeInclusive};
+
+use nalgebra::{Point3, Unit, UnitVector3};
+
+use crate::{core::aabb::AxisAlignedBoundingBox, material::MaterialType, ray::Ray, sampler};
+
+use super::{
+    hittable_object::{HitRecord, HittableObject},
+    quad::Quad,
+    rotate_y_decorator::RotateYDecorator,
+    sampleable_object::SampleableObject,
+    sphere::Sphere,
+    translate_decorator::TranslateDecorator,
+    HittableObjectType,
+};
+
+/// Emissive object, which can be sampled directly.
+///
+/// Moved and rotated lights keep the decorator wrapping only the light itself, which is hit,
+/// and the inner light, which is sampled in the decorator's object space. Decorators don't
+/// change lengths or angles, so densities with respect to solid angle stay the same.
+#[derive(Clone)]
+pub enum LightType {
+    Sphere(Sphere),
+    Quad(Quad),
+    Translated(TranslateDecorator, Box<LightType>),
+    RotatedY(RotateYDecorator, Box<LightType>),
+}
+
+impl LightType {
+    /// Returns the light as an object hit by rays.
+    fn to_object(&self) -> HittableObjectType {
+        match self {
+            LightType::Sphere(sphere) => sphere.clone().into(),
+            LightType::Quad(quad) => quad.clone().into(),
+            LightType::Translated(decorator, _) => decorator.clone().into(),
+            LightType::RotatedY(decorator, _) => decorator.clone().into(),
+        }
+    }
+}
+
+impl HittableObject for LightType {
+    fn hit(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> Option<HitRecord> {
+        match self {
+            LightType::Sphere(sphere) => sphere.hit(ray, t_range),
+            LightType::Quad(quad) => quad.hit(ray, t_range),
+            LightType::Translated(decorator, _) => decorator.hit(ray, t_range),
+            LightType::RotatedY(decorator, _) => decorator.hit(ray, t_range),
+        }
+    }
+
+    fn occluded(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> bool {
+        match self {
+            LightType::Sphere(sphere) => sphere.occluded(ray, t_range),
+            LightType::Quad(quad) => quad.occluded(ray, t_range),
+            LightType::Translated(decorator, _) => decorator.occluded(ray, t_range),
+            LightType::RotatedY(decorator, _) => decorator.occluded(ray, t_range),
+        }
+    }
+
+    fn bounding_box(&self) -> &AxisAlignedBoundingBox {
+        match self {
+            LightType::Sphere(sphere) => sphere.bounding_box(),
+            LightType::Quad(quad) => quad.bounding_box(),
+            LightType::Translated(decorator, _) => decorator.bounding_box(),
+            LightType::RotatedY(decorator, _) => decorator.bounding_box(),
+        }
+    }
+}
+
+impl SampleableObject for LightType {
+    fn pdf_value(&self, origin: &Point3<f64>, direction: &UnitVector3<f64>) -> f64 {
+        match self {
+            LightType::Sphere(sphere) => sphere.pdf_value(origin, direction),
+            LightType::Quad(quad) => quad.pdf_value(origin, direction),
+            LightType::Translated(decorator, light) => {
+                light.pdf_value(&(origin - decorator.offset()), direction)
+            }
+            LightType::RotatedY(decorator, light) => {
+                let origin = Point3::from(decorator.to_object_space(&origin.coords));
+                let direction = Unit::new_unchecked(decorator.to_object_space(direction));
+                light.pdf_value(&origin, &direction)
+            }
+        }
+    }
+
+    fn random_direction(&self, origin: &Point3<f64>) -> UnitVector3<f64> {
+        match self {
+            LightType::Sphere(sphere) => sphere.random_direction(origin),
+            LightType::Quad(quad) => quad.random_direction(origin),
+            LightType::Translated(decorator, light) => {
+                light.random_direction(&(origin - decorator.offset()))
+            }
+            LightType::RotatedY(decorator, light) => {
+                let origin = Point3::from(decorator.to_object_space(&origin.coords));
+                let direction = light.random_direction(&origin);
+                Unit::new_unchecked(decorator.to_world_space(&direction))
+            }
+        }
+    }
+}
+
+/// All lights of the scene, each of them is sampled with the same probability.
+///
+/// Only spheres and quads with `DiffuseLight` material (also the ones nested in lists, moved
+/// or rotated) are collected, other emissive objects are still lit by rays that hit them by chance.
+#[derive(Clone, Default)]
+pub struct LightList {
+    lights: Vec<LightType>,
+}
+
+impl LightList {
+    pub fn new<T: Borrow<HittableObjectType>>(objects: &[T], materials: &[MaterialType]) -> Self {
+        let mut lights = vec![];
+        Self::collect_lights(objects, materials, &mut lights);
+        LightList { lights }
+    }
+
+    pub fn lights(&self) -> &[LightType] {
+        &self.lights
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    /// Returns the closest hit of the `ray` with any of the lights, ignoring all other objects.
+    pub fn hit(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> Option<HitRecord> {
+        let mut closest_hit: Option<HitRecord> = None;
+        let mut closest_t = *t_range.end();
+        for light in &self.lights {
+            if let Some(hit_record) = light.hit(ray, &(*t_range.start()..=closest_t)) {
+                closest_t = hit_record.t();
+                closest_hit = Some(hit_record);
+            }
+        }
+        closest_hit
+    }
+
+    fn collect_lights<T: Borrow<HittableObjectType>>(
+        objects: &[T],
+        materials: &[MaterialType],
+        lights: &mut Vec<LightType>,
+    ) {
+        let is_emissive = |material_id: usize| {
+            matches!(
+                materials.get(material_id),
+                Some(MaterialType::DiffuseLight(_))
+            )
+        };
+        for object in objects {
+            match object.borrow() {
+                HittableObjectType::Sphere(sphere) if is_emissive(sphere.material_id()) => {
+                    lights.push(LightType::Sphere(sphere.clone()))
+                }
+                HittableObjectType::Quad(quad) if is_emissive(quad.material_id()) => {
+                    lights.push(LightType::Quad(quad.clone()))
+                }
+                HittableObjectType::HittableObjectList(list) => {
+                    Self::collect_lights(list.items(), materials, lights)
+                }
+                // Every light inside the decorator gets its own decorator, so other objects
+                // wrapped together with it aren't hit when looking for the light
+                HittableObjectType::TranslateDecorator(decorator) => {
+                    let mut inner_lights = vec![];
+                    Self::collect_lights(
+                        std::slice::from_ref(decorator.inner()),
+                        materials,
+                        &mut inner_lights,
+                    );
+                    lights.extend(inner_lights.into_iter().map(|light| {
+                        let moved = TranslateDecorator::new(light.to_object(), *decorator.offset());
+                        LightType::Translated(moved, Box::new(light))
+                    }));
+                }
+                HittableObjectType::RotateYDecorator(decorator) => {
+                    let mut inner_lights = vec![];
+                    Self::collect_lights(
+                        std::slice::from_ref(decorator.inner()),
+                        materials,
+                        &mut inner_lights,
+                    );
+                    lights.extend(inner_lights.into_iter().map(|light| {
+                        let rotated = RotateYDecorator::new(light.to_object(), decorator.angle());
+                        LightType::RotatedY(rotated, Box::new(light))
+                    }));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl SampleableObject for LightList {
+    fn pdf_value(&self, origin: &Point3<f64>, direction: &UnitVector3<f64>) -> f64 {
+        if self.lights.is_empty() {
+            return 0.0;
+        }
+        let pdf_sum: f64 = self
+            .lights
+            .iter()
+            .map(|light| light.pdf_value(origin, direction))
+            .sum();
+        pdf_sum / self.lights.len() as f64
+    }
+
+    /// Panics if there are no lights.
+    fn random_direction(&self, origin: &Point3<f64>) -> UnitVector3<f64> {
+        let index = (sampler::get_1d() * self.lights.len() as f64) as usize;
+        let light = &self.lights[index.min(self.lights.len() - 1)];
+        light.random_direction(origin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+    use rgb::Rgb;
+
+    use crate::material::diffuse_light::DiffuseLight;
+
+    use super::*;
+
+    #[test]
+    fn decorated_lights_are_sampled_like_transformed_ones() {
+        let materials = [DiffuseLight::from(Rgb::new(1.0, 1.0, 1.0)).into()];
+        let local_quad = Quad::new(
+            Point3::new(-1.0, 0.0, -1.0),
+            Vector3::new(2.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            0,
+        );
+        let decorated: HittableObjectType = TranslateDecorator::new(
+            RotateYDecorator::new(local_quad.into(), 90.0).into(),
+            Vector3::new(0.0, 5.0, 0.0),
+        )
+        .into();
+        // The same quad rotated by 90 degrees around Y axis and moved by 5 along it
+        let transformed: HittableObjectType = Quad::new(
+            Point3::new(-1.0, 5.0, 1.0),
+            Vector3::new(0.0, 0.0, -2.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            0,
+        )
+        .into();
+        let decorated_lights = LightList::new(&[decorated], &materials);
+        let transformed_lights = LightList::new(&[transformed], &materials);
+        assert_eq!(decorated_lights.lights().len(), 1);
+
+        let origin = Point3::new(0.3, 0.0, 0.2);
+        for _ in 0..1000 {
+            let direction = decorated_lights.random_direction(&origin);
+            let pdf = decorated_lights.pdf_value(&origin, &direction);
+            assert!(pdf > 0.0);
+            assert!((pdf - transformed_lights.pdf_value(&origin, &direction)).abs() < 1e-9 * pdf);
+            let ray = Ray::new(origin, direction.into_inner(), 0.0);
+            let hit = decorated_lights.hit(&ray, &(0.001..=f64::MAX)).unwrap();
+            assert!((hit.pos().y - 5.0).abs() < 1e-9);
+        }
+    }
+}
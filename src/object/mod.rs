@@ -8,23 +8,31 @@ use quad::Quad;
 use rotate_y_decorator::RotateYDecorator;
 use sphere::Sphere;
 use translate_decorator::TranslateDecorator;
+use triangle::Triangle;
+use triangle_mesh::TriangleMesh;
 
 use crate::{core::aabb::AxisAlignedBoundingBox, ray::Ray};
 
 pub mod constant_density_medium;
 pub mod hittable_object;
 pub mod hittable_objects_list;
+pub mod light;
 pub mod moving_sphere;
 pub mod quad;
 pub mod rotate_y_decorator;
+pub mod sampleable_object;
 pub mod sphere;
 pub mod translate_decorator;
+pub mod triangle;
+pub mod triangle_mesh;
 
 #[derive(Clone)]
 pub enum HittableObjectType {
     Sphere(Sphere),
     MovingSphere(MovingSphere),
     Quad(Quad),
+    Triangle(Triangle),
+    TriangleMesh(TriangleMesh),
     HittableObjectList(HittableObjectsList),
     TranslateDecorator(TranslateDecorator),
     RotateYDecorator(RotateYDecorator),
@@ -37,6 +45,8 @@ impl HittableObject for HittableObjectType {
             HittableObjectType::Sphere(sphere) => sphere.hit(ray, t_range),
             HittableObjectType::MovingSphere(moving_sphere) => moving_sphere.hit(ray, t_range),
             HittableObjectType::Quad(quad) => quad.hit(ray, t_range),
+            HittableObjectType::Triangle(triangle) => triangle.hit(ray, t_range),
+            HittableObjectType::TriangleMesh(triangle_mesh) => triangle_mesh.hit(ray, t_range),
             HittableObjectType::HittableObjectList(hittable_objects_list) => {
                 hittable_objects_list.hit(ray, t_range)
             }
@@ -52,11 +62,35 @@ impl HittableObject for HittableObjectType {
         }
     }
 
+    fn occluded(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> bool {
+        match self {
+            HittableObjectType::Sphere(sphere) => sphere.occluded(ray, t_range),
+            HittableObjectType::MovingSphere(moving_sphere) => moving_sphere.occluded(ray, t_range),
+            HittableObjectType::Quad(quad) => quad.occluded(ray, t_range),
+            HittableObjectType::Triangle(triangle) => triangle.occluded(ray, t_range),
+            HittableObjectType::TriangleMesh(triangle_mesh) => triangle_mesh.occluded(ray, t_range),
+            HittableObjectType::HittableObjectList(hittable_objects_list) => {
+                hittable_objects_list.occluded(ray, t_range)
+            }
+            HittableObjectType::TranslateDecorator(translate_decorator) => {
+                translate_decorator.occluded(ray, t_range)
+            }
+            HittableObjectType::RotateYDecorator(rotate_y_decorator) => {
+                rotate_y_decorator.occluded(ray, t_range)
+            }
+            HittableObjectType::ConstantDensityMedium(constant_density_medium) => {
+                constant_density_medium.occluded(ray, t_range)
+            }
+        }
+    }
+
     fn bounding_box(&self) -> &AxisAlignedBoundingBox {
         match self {
             HittableObjectType::Sphere(sphere) => sphere.bounding_box(),
             HittableObjectType::MovingSphere(moving_sphere) => moving_sphere.bounding_box(),
             HittableObjectType::Quad(quad) => quad.bounding_box(),
+            HittableObjectType::Triangle(triangle) => triangle.bounding_box(),
+            HittableObjectType::TriangleMesh(triangle_mesh) => triangle_mesh.bounding_box(),
             HittableObjectType::HittableObjectList(hittable_objects_list) => {
                 hittable_objects_list.bounding_box()
             }
@@ -91,6 +125,18 @@ impl From<Quad> for HittableObjectType {
     }
 }
 
+impl From<Triangle> for HittableObjectType {
+    fn from(value: Triangle) -> Self {
+        HittableObjectType::Triangle(value)
+    }
+}
+
+impl From<TriangleMesh> for HittableObjectType {
+    fn from(value: TriangleMesh) -> Self {
+        HittableObjectType::TriangleMesh(value)
+    }
+}
+
 impl From<HittableObjectsList> for HittableObjectType {
     fn from(value: HittableObjectsList) -> Self {
         HittableObjectType::HittableObjectList(value)
@@ -37,6 +37,22 @@ impl MovingSphere {
     pub fn center_at(&self, time: f64) -> Point3<f64> {
         self.from + time * self.direction
     }
+
+    pub fn from(&self) -> &Point3<f64> {
+        &self.from
+    }
+
+    pub fn to(&self) -> Point3<f64> {
+        self.from + self.direction
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    pub fn material_id(&self) -> usize {
+        self.material_id
+    }
 }
 
 impl HittableObject for MovingSphere {
@@ -45,6 +61,11 @@ impl HittableObject for MovingSphere {
         sphere_at_time.hit(ray, t_range)
     }
 
+    fn occluded(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> bool {
+        let sphere_at_time = Sphere::new(self.center_at(ray.time()), self.radius, self.material_id);
+        sphere_at_time.occluded(ray, t_range)
+    }
+
     fn bounding_box(&self) -> &AxisAlignedBoundingBox {
         &self.bounding_box
     }
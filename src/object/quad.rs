@@ -2,11 +2,19 @@ use std::ops::RangeInclusive;
 
 use nalgebra::{Point3, Unit, UnitVector3, Vector3};
 
-use crate::{core::aabb::AxisAlignedBoundingBox, ray::Ray};
+use crate::{
+    core::aabb::AxisAlignedBoundingBox,
+    ray::{
+        ray_packet::{RayPacket, PACKET_SIZE},
+        Ray,
+    },
+    sampler,
+};
 
 use super::{
     hittable_object::{HitRecord, HittableObject},
     hittable_objects_list::HittableObjectsList,
+    sampleable_object::SampleableObject,
 };
 
 /// `Quad` represents 2D quadrilateral (actually parallelogram, but quad sounds better).
@@ -33,15 +41,20 @@ pub struct Quad {
     normal: UnitVector3<f64>,
     plane_d: f64,
     w: Vector3<f64>,
+    area: f64,
 }
 
 impl Quad {
+    /// Rays more parallel to the plane of the quad than this never hit it
+    const PARALLEL_DELTA: f64 = 1e-8;
+
     pub fn new(start: Point3<f64>, u: Vector3<f64>, v: Vector3<f64>, material_id: usize) -> Self {
         let bb_diagonal_1 = AxisAlignedBoundingBox::new(start, start + u + v);
         let bb_diagonal_2 = AxisAlignedBoundingBox::new(start + u, start + v);
         let bounding_box = AxisAlignedBoundingBox::merge(&bb_diagonal_1, &bb_diagonal_2);
         let n = u.cross(&v);
         let w = n / n.dot(&n);
+        let area = n.norm();
         let normal = Unit::new_normalize(n);
         let plane_d = normal.dot(&start.coords);
         Self {
@@ -53,9 +66,26 @@ impl Quad {
             normal,
             plane_d,
             w,
+            area,
         }
     }
 
+    pub fn start(&self) -> &Point3<f64> {
+        &self.start
+    }
+
+    pub fn u(&self) -> &Vector3<f64> {
+        &self.u
+    }
+
+    pub fn v(&self) -> &Vector3<f64> {
+        &self.v
+    }
+
+    pub fn material_id(&self) -> usize {
+        self.material_id
+    }
+
     /// Returns `HittableObjectsList` that contains 6 quads, which creates cuboid.
     ///
     /// This cuboid is built on two opposite vertices - `start` and `end`.
@@ -112,15 +142,51 @@ impl Quad {
 
         vec![front, right, back, left, top, bottom].into()
     }
-}
 
-impl HittableObject for Quad {
-    fn hit(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> Option<HitRecord> {
+    /// Returns distances of the hits of all rays of the `packet` in the range from `t_min`
+    /// to their `t_max`. Works the same as `hit`, but computes all lanes at once and skips
+    /// the hit records.
+    pub fn hit_packet(
+        &self,
+        packet: &RayPacket,
+        t_min: f64,
+        t_max: &[f64; PACKET_SIZE],
+    ) -> [Option<f64>; PACKET_SIZE] {
+        let mut t = [0.0; PACKET_SIZE];
+        let mut hit = [false; PACKET_SIZE];
+        for lane in 0..PACKET_SIZE {
+            let origin = Vector3::new(
+                packet.origin(0)[lane],
+                packet.origin(1)[lane],
+                packet.origin(2)[lane],
+            );
+            let direction = Vector3::new(
+                packet.direction(0)[lane],
+                packet.direction(1)[lane],
+                packet.direction(2)[lane],
+            );
+            let denom = self.normal.dot(&direction);
+            t[lane] = (self.plane_d - self.normal.dot(&origin)) / denom;
+            let hitpoint_vector = origin + t[lane] * direction - self.start.coords;
+            let alpha = self.w.dot(&hitpoint_vector.cross(&self.v));
+            let beta = self.w.dot(&self.u.cross(&hitpoint_vector));
+            // Conditions are combined without branching, so the loop stays vectorized
+            hit[lane] = (denom.abs() >= Self::PARALLEL_DELTA)
+                & (t[lane] >= t_min)
+                & (t[lane] <= t_max[lane])
+                & (0.0..=1.0).contains(&alpha)
+                & (0.0..=1.0).contains(&beta);
+        }
+        std::array::from_fn(|lane| hit[lane].then_some(t[lane]))
+    }
+
+    /// Returns distance of the hit of the `ray` in `t_range`, together with its coordinates
+    /// `(alpha, beta)` along `u` and `v`.
+    fn hit_coords(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> Option<(f64, f64, f64)> {
         let denom = self.normal.dot(ray.direction());
 
         // Ray is parallel to the plane on which quad lies
-        const DELTA: f64 = 1e-8;
-        if denom.abs() < DELTA {
+        if denom.abs() < Self::PARALLEL_DELTA {
             return None;
         }
 
@@ -144,12 +210,43 @@ impl HittableObject for Quad {
             return None;
         }
 
+        Some((t, alpha, beta))
+    }
+}
+
+impl HittableObject for Quad {
+    fn hit(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> Option<HitRecord> {
+        let (t, alpha, beta) = self.hit_coords(ray, t_range)?;
+        let pos = ray.at(t);
         let hit_record = HitRecord::new(pos, t, self.normal, ray, self.material_id, alpha, beta);
 
         Some(hit_record)
     }
 
+    fn occluded(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> bool {
+        self.hit_coords(ray, t_range).is_some()
+    }
+
     fn bounding_box(&self) -> &AxisAlignedBoundingBox {
         &self.bounding_box
     }
 }
+
+impl SampleableObject for Quad {
+    fn pdf_value(&self, origin: &Point3<f64>, direction: &UnitVector3<f64>) -> f64 {
+        let ray = Ray::new(*origin, direction.into_inner(), 0.0);
+        let Some(hit_record) = self.hit(&ray, &(0.001..=f64::INFINITY)) else {
+            return 0.0;
+        };
+        // Convert density with respect to area into density with respect to solid angle
+        let distance_squared = hit_record.t() * hit_record.t();
+        let cosine = direction.dot(&self.normal).abs();
+        distance_squared / (cosine * self.area)
+    }
+
+    fn random_direction(&self, origin: &Point3<f64>) -> UnitVector3<f64> {
+        let u = sampler::get_2d();
+        let point = self.start + u.x * self.u + u.y * self.v;
+        Unit::new_normalize(point - origin)
+    }
+}
@@ -16,6 +16,8 @@ use super::{
 pub struct RotateYDecorator {
     bbox: AxisAlignedBoundingBox,
     inner: Box<HittableObjectType>,
+    /// Rotation angle in degrees
+    angle: f64,
     sin_theta: f64,
     cos_theta: f64,
 }
@@ -70,6 +72,7 @@ impl RotateYDecorator {
         RotateYDecorator {
             bbox: final_bbox,
             inner: Box::new(inner),
+            angle,
             cos_theta,
             sin_theta,
         }
@@ -78,42 +81,47 @@ impl RotateYDecorator {
     pub fn inner(&self) -> &HittableObjectType {
         &self.inner
     }
-}
 
-impl HittableObject for RotateYDecorator {
-    fn hit(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> Option<HitRecord> {
-        // Change ray coords from world space to object space
-        let origin = Point3::new(
-            self.cos_theta * ray.origin().x - self.sin_theta * ray.origin().z,
-            ray.origin().y,
-            self.sin_theta * ray.origin().x + self.cos_theta * ray.origin().z,
-        );
+    /// Returns rotation angle in degrees
+    pub fn angle(&self) -> f64 {
+        self.angle
+    }
 
-        let direction = Vector3::new(
-            self.cos_theta * ray.direction().x - self.sin_theta * ray.direction().z,
-            ray.direction().y,
-            self.sin_theta * ray.direction().x + self.cos_theta * ray.direction().z,
-        );
+    /// Rotates `vector` from world space to object space.
+    pub fn to_object_space(&self, vector: &Vector3<f64>) -> Vector3<f64> {
+        Vector3::new(
+            self.cos_theta * vector.x - self.sin_theta * vector.z,
+            vector.y,
+            self.sin_theta * vector.x + self.cos_theta * vector.z,
+        )
+    }
 
-        let rotated_ray = Ray::new(origin, direction, ray.time());
+    /// Rotates `vector` from object space back to world space.
+    pub fn to_world_space(&self, vector: &Vector3<f64>) -> Vector3<f64> {
+        Vector3::new(
+            self.cos_theta * vector.x + self.sin_theta * vector.z,
+            vector.y,
+            -self.sin_theta * vector.x + self.cos_theta * vector.z,
+        )
+    }
 
-        let hr = self.inner().hit(&rotated_ray, t_range);
+    /// Changes ray coords from world space to object space
+    fn rotated_ray(&self, ray: &Ray) -> Ray {
+        let origin = Point3::from(self.to_object_space(&ray.origin().coords));
+        let direction = self.to_object_space(ray.direction());
+        Ray::new(origin, direction, ray.time())
+    }
+}
+
+impl HittableObject for RotateYDecorator {
+    fn hit(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> Option<HitRecord> {
+        let hr = self.inner().hit(&self.rotated_ray(ray), t_range);
 
         match hr {
             Some(hit_record) => {
                 // Position back from object space to world space
-                let new_pos = Point3::new(
-                    self.cos_theta * hit_record.pos().x + self.sin_theta * hit_record.pos().z,
-                    hit_record.pos().y,
-                    -self.sin_theta * hit_record.pos().x + self.cos_theta * hit_record.pos().z,
-                );
-
-                let new_normal = Unit::new_normalize(Vector3::new(
-                    self.cos_theta * hit_record.normal().x + self.sin_theta * hit_record.normal().z,
-                    hit_record.normal().y,
-                    -self.sin_theta * hit_record.normal().x
-                        + self.cos_theta * hit_record.normal().z,
-                ));
+                let new_pos = Point3::from(self.to_world_space(&hit_record.pos().coords));
+                let new_normal = Unit::new_normalize(self.to_world_space(hit_record.normal()));
 
                 Some(HitRecord::with_changed_pos_and_normal(
                     new_pos,
@@ -125,6 +133,10 @@ impl HittableObject for RotateYDecorator {
         }
     }
 
+    fn occluded(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> bool {
+        self.inner().occluded(&self.rotated_ray(ray), t_range)
+    }
+
     fn bounding_box(&self) -> &AxisAlignedBoundingBox {
         &self.bbox
     }
@@ -0,0 +1,9 @@
+use nalgebra::{Point3, UnitVector3};
+
+/// Object towards which directions can be sampled, used for sampling lights.
+pub trait SampleableObject {
+    /// Returns probability density (with respect to solid angle) of sampling `direction` from `origin`.
+    fn pdf_value(&self, origin: &Point3<f64>, direction: &UnitVector3<f64>) -> f64;
+    /// Returns random direction from `origin` towards the object.
+    fn random_direction(&self, origin: &Point3<f64>) -> UnitVector3<f64>;
+}
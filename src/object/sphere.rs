@@ -3,11 +3,20 @@ use std::{f64, ops::RangeInclusive};
 use nalgebra::{Point3, Unit, UnitVector3, Vector3};
 
 use crate::{
-    core::aabb::AxisAlignedBoundingBox, extensions::ri_surrounds::RangeInclusiveSurroundsExtension,
-    ray::Ray,
+    core::aabb::AxisAlignedBoundingBox,
+    extensions::ri_surrounds::RangeInclusiveSurroundsExtension,
+    ray::{
+        ray_packet::{RayPacket, PACKET_SIZE},
+        Ray,
+    },
+    sampler,
+    utils::{orthonormal_basis::OrthonormalBasis, random_vector_generator},
 };
 
-use super::hittable_object::{HitRecord, HittableObject};
+use super::{
+    hittable_object::{HitRecord, HittableObject},
+    sampleable_object::SampleableObject,
+};
 
 #[derive(Clone)]
 pub struct Sphere {
@@ -38,21 +47,53 @@ impl Sphere {
         self.radius
     }
 
-    /// It returns pair `(u, v)`, where
-    /// `u` is value `[0, 1]` of angle around the Y axis from X=-1,
-    /// `v` is value `[0, 1]` of angle from Y=-1 to Y=1.
-    /// `pos` should be a vector from center to surface of the sphere of radius one.
-    fn uv_coords(pos: &UnitVector3<f64>) -> (f64, f64) {
-        let theta = (-pos.y).acos();
-        let phi = (-pos.z).atan2(pos.x) + f64::consts::PI;
-        let u = phi / (2.0 * f64::consts::PI);
-        let v = theta / f64::consts::PI;
-        (u, v)
+    pub fn material_id(&self) -> usize {
+        self.material_id
     }
-}
 
-impl HittableObject for Sphere {
-    fn hit(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> Option<HitRecord> {
+    /// Returns distances of the hits of all rays of the `packet` in the range from `t_min`
+    /// to their `t_max`. Works the same as `hit`, but computes all lanes at once and skips
+    /// the hit records.
+    pub fn hit_packet(
+        &self,
+        packet: &RayPacket,
+        t_min: f64,
+        t_max: &[f64; PACKET_SIZE],
+    ) -> [Option<f64>; PACKET_SIZE] {
+        let mut roots = [0.0; PACKET_SIZE];
+        for (lane, root) in roots.iter_mut().enumerate() {
+            let oc = self.center()
+                - Point3::new(
+                    packet.origin(0)[lane],
+                    packet.origin(1)[lane],
+                    packet.origin(2)[lane],
+                );
+            let direction = Vector3::new(
+                packet.direction(0)[lane],
+                packet.direction(1)[lane],
+                packet.direction(2)[lane],
+            );
+            let a = direction.norm_squared();
+            let h = direction.dot(&oc);
+            let c = oc.norm_squared() - self.radius() * self.radius();
+            let delta = h * h - a * c;
+            // Square root of negative `delta` is NaN, which is outside of every range
+            let delta_sqrt = delta.sqrt();
+            let near = (h - delta_sqrt) / a;
+            let far = (h + delta_sqrt) / a;
+            // Far root is used when the near one is out of range, e.g. from inside the sphere.
+            // Both are computed, so the choice is a select instead of a branch.
+            let near_in_range = (near > t_min) & (near < t_max[lane]);
+            *root = if near_in_range { near } else { far };
+        }
+        std::array::from_fn(|lane| {
+            let root = roots[lane];
+            ((root > t_min) & (root < t_max[lane])).then_some(root)
+        })
+    }
+
+    /// Returns distance of the hit of the `ray` in `t_range`.
+    fn hit_distance(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> Option<f64> {
         let oc = self.center() - ray.origin();
         let a = ray.direction().norm_squared();
         let h = ray.direction().dot(&oc);
@@ -66,12 +107,30 @@ impl HittableObject for Sphere {
         // Find nearest root that is in acceptable range
         let mut root = (h - delta_sqrt) / a;
         if !t_range.surrounds(&root) {
-            root = (h - delta_sqrt) / a;
+            root = (h + delta_sqrt) / a;
             if !t_range.surrounds(&root) {
                 return None;
             }
         }
+        Some(root)
+    }
 
+    /// It returns pair `(u, v)`, where
+    /// `u` is value `[0, 1]` of angle around the Y axis from X=-1,
+    /// `v` is value `[0, 1]` of angle from Y=-1 to Y=1.
+    /// `pos` should be a vector from center to surface of the sphere of radius one.
+    fn uv_coords(pos: &UnitVector3<f64>) -> (f64, f64) {
+        let theta = (-pos.y).acos();
+        let phi = (-pos.z).atan2(pos.x) + f64::consts::PI;
+        let u = phi / (2.0 * f64::consts::PI);
+        let v = theta / f64::consts::PI;
+        (u, v)
+    }
+}
+
+impl HittableObject for Sphere {
+    fn hit(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> Option<HitRecord> {
+        let root = self.hit_distance(ray, t_range)?;
         let hit_point = ray.at(root);
         // This is unit vector thanks to dividing it by radius
         let outward_normal = (hit_point - self.center()) / self.radius();
@@ -92,7 +151,92 @@ impl HittableObject for Sphere {
         Some(hit_record)
     }
 
+    fn occluded(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> bool {
+        self.hit_distance(ray, t_range).is_some()
+    }
+
     fn bounding_box(&self) -> &AxisAlignedBoundingBox {
         &self.bounding_box
     }
 }
+
+/// Directions are sampled uniformly from the cone of directions in which the sphere is visible.
+/// From the inside of the sphere, all directions are sampled uniformly, as every one of them
+/// hits the sphere at its far root.
+impl SampleableObject for Sphere {
+    fn pdf_value(&self, origin: &Point3<f64>, direction: &UnitVector3<f64>) -> f64 {
+        let ray = Ray::new(*origin, direction.into_inner(), 0.0);
+        if !self.occluded(&ray, &(0.001..=f64::INFINITY)) {
+            return 0.0;
+        }
+        let distance_squared = (self.center - origin).norm_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            return 1.0 / (4.0 * f64::consts::PI);
+        }
+        let cos_theta_max = (1.0 - radius_squared / distance_squared).sqrt();
+        let solid_angle = 2.0 * f64::consts::PI * (1.0 - cos_theta_max);
+        1.0 / solid_angle
+    }
+
+    fn random_direction(&self, origin: &Point3<f64>) -> UnitVector3<f64> {
+        let to_center = self.center - origin;
+        let distance_squared = to_center.norm_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            return random_vector_generator::sphere_direction(&sampler::get_2d());
+        }
+
+        let u = sampler::get_2d();
+        let cos_theta_max = (1.0 - radius_squared / distance_squared).sqrt();
+        let z = 1.0 + u.x * (cos_theta_max - 1.0);
+        let phi = 2.0 * f64::consts::PI * u.y;
+        let sin_theta = (1.0 - z * z).max(0.0).sqrt();
+        let local = Vector3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, z);
+
+        let basis = OrthonormalBasis::new(Unit::new_normalize(to_center));
+        Unit::new_normalize(basis.local_to_world(&local))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector2;
+
+    use super::*;
+
+    /// Estimates integral of `pdf_value` over all directions from `origin` with a grid
+    /// of uniformly distributed directions.
+    fn pdf_integral(sphere: &Sphere, origin: &Point3<f64>) -> f64 {
+        const STEPS: usize = 200;
+        let mut sum = 0.0;
+        for i in 0..STEPS {
+            for j in 0..STEPS {
+                let u = Vector2::new(i as f64 + 0.5, j as f64 + 0.5) / STEPS as f64;
+                let direction = random_vector_generator::sphere_direction(&u);
+                sum += sphere.pdf_value(origin, &direction);
+            }
+        }
+        sum * 4.0 * f64::consts::PI / (STEPS * STEPS) as f64
+    }
+
+    #[test]
+    fn pdf_integrates_to_one_from_outside_and_inside() {
+        let sphere = Sphere::new(Point3::new(1.0, 2.0, 3.0), 2.0, 0);
+        for origin in [Point3::new(6.0, 2.0, 3.0), Point3::new(1.5, 2.5, 3.0)] {
+            let integral = pdf_integral(&sphere, &origin);
+            assert!((integral - 1.0).abs() < 0.01, "integral is {integral}");
+        }
+    }
+
+    #[test]
+    fn sampled_directions_have_positive_pdf() {
+        let sphere = Sphere::new(Point3::new(1.0, 2.0, 3.0), 2.0, 0);
+        for origin in [Point3::new(6.0, 2.0, 3.0), Point3::new(1.5, 2.5, 3.0)] {
+            for _ in 0..1000 {
+                let direction = sphere.random_direction(&origin);
+                assert!(sphere.pdf_value(&origin, &direction) > 0.0);
+            }
+        }
+    }
+}
@@ -29,19 +29,25 @@ impl TranslateDecorator {
     pub fn inner(&self) -> &HittableObjectType {
         &self.inner
     }
-}
 
-impl HittableObject for TranslateDecorator {
-    fn hit(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> Option<HitRecord> {
+    pub fn offset(&self) -> &Vector3<f64> {
+        &self.offset
+    }
+
+    fn moved_ray(&self, ray: &Ray) -> Ray {
         // Instead of moving object we move ray (camera) in oposite direction
         // So we need to change ray origin
-        let moved_ray = Ray::new(
+        Ray::new(
             *ray.origin() - self.offset,
             ray.direction().into_inner(),
             ray.time(),
-        );
+        )
+    }
+}
 
-        let hr = self.inner().hit(&moved_ray, t_range);
+impl HittableObject for TranslateDecorator {
+    fn hit(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> Option<HitRecord> {
+        let hr = self.inner().hit(&self.moved_ray(ray), t_range);
 
         match hr {
             Some(hit_record) => {
@@ -53,6 +59,10 @@ impl HittableObject for TranslateDecorator {
         }
     }
 
+    fn occluded(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> bool {
+        self.inner().occluded(&self.moved_ray(ray), t_range)
+    }
+
     fn bounding_box(&self) -> &AxisAlignedBoundingBox {
         &self.bbox
     }
@@ -0,0 +1,171 @@
+use std::{ops::RangeInclusive, sync::Arc};
+
+use nalgebra::{Point3, Unit, UnitVector3, Vector2, Vector3};
+
+use crate::{core::aabb::AxisAlignedBoundingBox, ray::Ray};
+
+use super::{
+    hittable_object::{HitRecord, HittableObject},
+    triangle_mesh::MeshData,
+};
+
+/// `Triangle` is a single face of `MeshData`. Standalone triangles just own a mesh with one face.
+///
+/// Texture coordinates of the hit are interpolated from mesh's per-vertex `uvs`, or if they
+/// are missing, barycentric coordinates of the hit point are used instead.
+/// If mesh has per-vertex normals, they are interpolated as well (smooth shading).
+#[derive(Clone)]
+pub struct Triangle {
+    mesh: Arc<MeshData>,
+    face: usize,
+    material_id: usize,
+    bounding_box: AxisAlignedBoundingBox,
+}
+
+impl Triangle {
+    pub fn new(a: Point3<f64>, b: Point3<f64>, c: Point3<f64>, material_id: usize) -> Self {
+        let mesh = MeshData::new(vec![a, b, c], None, None, vec![[0, 1, 2]]);
+        Self::from_mesh(Arc::new(mesh), 0, material_id)
+    }
+
+    /// Creates triangle with per-vertex `normals`, which are interpolated across its surface.
+    pub fn with_normals(
+        a: Point3<f64>,
+        b: Point3<f64>,
+        c: Point3<f64>,
+        normals: [Vector3<f64>; 3],
+        material_id: usize,
+    ) -> Self {
+        let mesh = MeshData::new(vec![a, b, c], Some(normals.to_vec()), None, vec![[0, 1, 2]]);
+        Self::from_mesh(Arc::new(mesh), 0, material_id)
+    }
+
+    /// Creates triangle from `face`-th face of the `mesh`.
+    pub fn from_mesh(mesh: Arc<MeshData>, face: usize, material_id: usize) -> Self {
+        let [a, b, c] = mesh.faces()[face].map(|id| mesh.vertices()[id]);
+        let bb_ab = AxisAlignedBoundingBox::new(a, b);
+        let bb_c = AxisAlignedBoundingBox::new(c, c);
+        let bounding_box = AxisAlignedBoundingBox::merge(&bb_ab, &bb_c);
+        Triangle {
+            mesh,
+            face,
+            material_id,
+            bounding_box,
+        }
+    }
+
+    pub fn vertices(&self) -> [Point3<f64>; 3] {
+        self.indices().map(|id| self.mesh.vertices()[id])
+    }
+
+    pub fn normals(&self) -> Option<[UnitVector3<f64>; 3]> {
+        let normals = self.mesh.normals()?;
+        Some(self.indices().map(|id| normals[id]))
+    }
+
+    pub fn uvs(&self) -> Option<[Vector2<f64>; 3]> {
+        let uvs = self.mesh.uvs()?;
+        Some(self.indices().map(|id| uvs[id]))
+    }
+
+    pub fn material_id(&self) -> usize {
+        self.material_id
+    }
+
+    fn indices(&self) -> [usize; 3] {
+        self.mesh.faces()[self.face]
+    }
+
+    /// Returns distance of the hit of the `ray` in `t_range`, together with its barycentric
+    /// coordinates `(beta, gamma)` relative to the second and third vertex.
+    fn hit_coords(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> Option<(f64, f64, f64)> {
+        // Möller–Trumbore intersection algorithm
+        let [a, b, c] = self.vertices();
+        let edge_1 = b - a;
+        let edge_2 = c - a;
+
+        let p = ray.direction().cross(&edge_2);
+        let determinant = edge_1.dot(&p);
+
+        // Ray is parallel to the plane on which triangle lies (or triangle is degenerate).
+        // Determinant is `|edge_1 × edge_2| * |direction|` times cosine of the angle between
+        // the ray and the normal, so it's compared relative to them to not depend on the scale.
+        const DELTA: f64 = 1e-8;
+        let scale = edge_1.cross(&edge_2).norm() * ray.direction().norm();
+        if determinant.abs() <= DELTA * scale {
+            return None;
+        }
+        let inversed_determinant = 1.0 / determinant;
+
+        // `beta` and `gamma` are barycentric coordinates of the hit point
+        // relative to `b` and `c` respectively
+        let s = ray.origin() - a;
+        let beta = s.dot(&p) * inversed_determinant;
+        if !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+
+        let q = s.cross(&edge_1);
+        let gamma = ray.direction().dot(&q) * inversed_determinant;
+        if gamma < 0.0 || beta + gamma > 1.0 {
+            return None;
+        }
+
+        let t = edge_2.dot(&q) * inversed_determinant;
+        if !t_range.contains(&t) {
+            return None;
+        }
+        Some((t, beta, gamma))
+    }
+}
+
+impl HittableObject for Triangle {
+    fn hit(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> Option<HitRecord> {
+        let (t, beta, gamma) = self.hit_coords(ray, t_range)?;
+        let [a, b, c] = self.vertices();
+        let edge_1 = b - a;
+        let edge_2 = c - a;
+
+        let alpha = 1.0 - beta - gamma;
+        let geometric_normal = Unit::new_normalize(edge_1.cross(&edge_2));
+        let indices = self.indices();
+
+        let normal = match self.mesh.normals() {
+            Some(normals) => {
+                let interpolated = Unit::new_normalize(
+                    alpha * normals[indices[0]].into_inner()
+                        + beta * normals[indices[1]].into_inner()
+                        + gamma * normals[indices[2]].into_inner(),
+                );
+                // Shading normal must point to the same side as the geometric one,
+                // otherwise `front_face` would be inconsistent with the surface.
+                if interpolated.dot(&geometric_normal) < 0.0 {
+                    -interpolated
+                } else {
+                    interpolated
+                }
+            }
+            None => geometric_normal,
+        };
+
+        let (u, v) = match self.mesh.uvs() {
+            Some(uvs) => {
+                let uv = alpha * uvs[indices[0]] + beta * uvs[indices[1]] + gamma * uvs[indices[2]];
+                (uv.x, uv.y)
+            }
+            None => (beta, gamma),
+        };
+
+        let pos = ray.at(t);
+        let hit_record = HitRecord::new(pos, t, normal, ray, self.material_id, u, v);
+        Some(hit_record)
+    }
+
+    fn occluded(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> bool {
+        self.hit_coords(ray, t_range).is_some()
+    }
+
+    fn bounding_box(&self) -> &AxisAlignedBoundingBox {
+        &self.bounding_box
+    }
+}
@@ -0,0 +1,113 @@
+use std::{ops::RangeInclusive, sync::Arc, time::Instant};
+
+use log::debug;
+use nalgebra::{Point3, Unit, UnitVector3, Vector2, Vector3};
+
+use crate::{core::aabb::AxisAlignedBoundingBox, core::bvh::BvhTree, ray::Ray};
+
+use super::{
+    hittable_object::{HitRecord, HittableObject},
+    triangle::Triangle,
+    HittableObjectType,
+};
+
+/// Vertex and index buffers shared by all triangles of a single mesh.
+///
+/// `normals` and `uvs` (if present) are indexed the same way as `vertices`.
+pub struct MeshData {
+    vertices: Vec<Point3<f64>>,
+    /// Per-vertex normals, used for smooth shading
+    normals: Option<Vec<UnitVector3<f64>>>,
+    /// Per-vertex texture coordinates
+    uvs: Option<Vec<Vector2<f64>>>,
+    /// Each face is a triple of indices into `vertices`
+    faces: Vec<[usize; 3]>,
+}
+
+impl MeshData {
+    pub fn new(
+        vertices: Vec<Point3<f64>>,
+        normals: Option<Vec<Vector3<f64>>>,
+        uvs: Option<Vec<Vector2<f64>>>,
+        faces: Vec<[usize; 3]>,
+    ) -> Self {
+        assert!(faces.iter().flatten().all(|&id| id < vertices.len()));
+        assert!(normals.as_ref().is_none_or(|n| n.len() == vertices.len()));
+        assert!(uvs.as_ref().is_none_or(|uv| uv.len() == vertices.len()));
+        let normals = normals.map(|normals| normals.into_iter().map(Unit::new_normalize).collect());
+        MeshData {
+            vertices,
+            normals,
+            uvs,
+            faces,
+        }
+    }
+
+    pub fn vertices(&self) -> &[Point3<f64>] {
+        &self.vertices
+    }
+
+    pub fn normals(&self) -> Option<&[UnitVector3<f64>]> {
+        self.normals.as_deref()
+    }
+
+    pub fn uvs(&self) -> Option<&[Vector2<f64>]> {
+        self.uvs.as_deref()
+    }
+
+    pub fn faces(&self) -> &[[usize; 3]] {
+        &self.faces
+    }
+}
+
+/// `TriangleMesh` is a collection of triangles sharing the same `MeshData` and material.
+///
+/// Triangles are stored in their own `BvhTree`, so even huge meshes are just a single
+/// leaf in the scene's tree.
+#[derive(Clone)]
+pub struct TriangleMesh {
+    mesh: Arc<MeshData>,
+    material_id: usize,
+    bvh: Arc<BvhTree>,
+}
+
+impl TriangleMesh {
+    pub fn new(mesh: MeshData, material_id: usize) -> Self {
+        assert!(!mesh.faces.is_empty());
+        let mesh = Arc::new(mesh);
+        let triangles: Vec<HittableObjectType> = (0..mesh.faces.len())
+            .map(|face| Triangle::from_mesh(mesh.clone(), face, material_id).into())
+            .collect();
+        let start = Instant::now();
+        let bvh = BvhTree::from(triangles);
+        debug!("Built mesh BVH in {:.2?}: {}", start.elapsed(), bvh.stats());
+        let bvh = Arc::new(bvh);
+        TriangleMesh {
+            mesh,
+            material_id,
+            bvh,
+        }
+    }
+
+    pub fn mesh(&self) -> &MeshData {
+        &self.mesh
+    }
+
+    pub fn material_id(&self) -> usize {
+        self.material_id
+    }
+}
+
+impl HittableObject for TriangleMesh {
+    fn hit(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> Option<HitRecord> {
+        self.bvh.hit(ray, t_range)
+    }
+
+    fn occluded(&self, ray: &Ray, t_range: &RangeInclusive<f64>) -> bool {
+        self.bvh.occluded(ray, t_range)
+    }
+
+    fn bounding_box(&self) -> &AxisAlignedBoundingBox {
+        self.bvh.bounding_box()
+    }
+}
@@ -1,4 +1,5 @@
 pub mod ray_generator;
+pub mod ray_packet;
 
 use nalgebra::{Point3, Unit, UnitVector3, Vector3};
 
@@ -6,6 +7,8 @@ use nalgebra::{Point3, Unit, UnitVector3, Vector3};
 pub struct Ray {
     origin: Point3<f64>,
     direction: UnitVector3<f64>,
+    /// Component-wise inverse of `direction`, precomputed for intersecting bounding boxes
+    inverse_direction: Vector3<f64>,
     /// `time` represents moment when ray was sent
     time: f64,
 }
@@ -16,6 +19,7 @@ impl Ray {
         Ray {
             origin,
             direction,
+            inverse_direction: direction.map(|d| 1.0 / d),
             time,
         }
     }
@@ -28,6 +32,10 @@ impl Ray {
         &self.direction
     }
 
+    pub fn inverse_direction(&self) -> &Vector3<f64> {
+        &self.inverse_direction
+    }
+
     pub fn time(&self) -> f64 {
         self.time
     }
@@ -1,29 +1,20 @@
-use rand::Rng;
-
 use crate::{
     extensions::{
         vector_reflection::VectorReflectionExtension, vector_refraction::VectorRefractionExtension,
     },
     object::hittable_object::HitRecord,
-    utils::random_vector_generator,
+    sampler,
+    utils::{orthonormal_basis::OrthonormalBasis, random_vector_generator},
 };
 
 use super::Ray;
 
 /// Returns a random `Ray` that is on the same hemisphere as
-/// the normal of the `hit_record`.
+/// the normal of the `hit_record`, with cosine-weighted distribution around the normal.
 pub fn random_ray_on_hemisphere(ray: &Ray, hit_record: &HitRecord) -> Ray {
-    let random_unit = random_vector_generator::random_unit_vector3_in_sphere().into_inner();
-    let scatter_direction = hit_record.normal().into_inner() + random_unit;
-    // Check for degenerate case (near-zero vector)
-    if scatter_direction.iter().all(|x| x.abs() < 1e-8) {
-        // In this case just use the normal
-        return Ray::new(
-            *hit_record.pos(),
-            hit_record.normal().into_inner(),
-            ray.time(),
-        );
-    }
+    let basis = OrthonormalBasis::new(*hit_record.normal());
+    let local_direction = random_vector_generator::cosine_direction(&sampler::get_2d());
+    let scatter_direction = basis.local_to_world(&local_direction);
     Ray::new(*hit_record.pos(), scatter_direction, ray.time())
 }
 
@@ -37,7 +28,7 @@ pub fn fuzzed_ray(ray: &Ray, fuzziness: f64) -> Ray {
         return *ray;
     }
     let fuzzed_direction = ray.direction().normalize()
-        + fuzziness * random_vector_generator::random_unit_vector3_in_sphere().into_inner();
+        + fuzziness * random_vector_generator::sphere_direction(&sampler::get_2d()).into_inner();
     Ray::new(*ray.origin(), fuzzed_direction, ray.time())
 }
 
@@ -56,8 +47,7 @@ pub fn refracted_ray(
 
     let cannot_refract = refraction_index * sin_theta > 1.0;
 
-    let mut rng = rand::rng();
-    if cannot_refract || schlick_reflectance(cos_theta, refraction_index) > rng.random() {
+    if cannot_refract || schlick_reflectance(cos_theta, refraction_index) > sampler::get_1d() {
         return None;
     }
 
@@ -68,7 +58,7 @@ pub fn refracted_ray(
 }
 
 pub fn ray_in_random_unit_direction(ray: &Ray, hit_record: &HitRecord) -> Ray {
-    let direction = random_vector_generator::random_unit_vector3_in_sphere();
+    let direction = random_vector_generator::sphere_direction(&sampler::get_2d());
     Ray::new(*hit_record.pos(), direction.into_inner(), ray.time())
 }
 
@@ -0,0 +1,67 @@
+use super::Ray;
+
+/// Maximum number of rays in a packet
+pub const PACKET_SIZE: usize = 8;
+
+/// Rays traced through the scene together, e.g. camera rays of neighbouring samples,
+/// which are coherent enough to visit mostly the same BVH nodes.
+///
+/// Components of the rays are stored as arrays with a lane per ray, so that loops over
+/// the lanes compile into SIMD instructions. Lanes past `len` repeat the first ray.
+pub struct RayPacket {
+    rays: [Ray; PACKET_SIZE],
+    len: usize,
+    /// Components of origins, directions and inverse directions, one array per axis
+    origin: [[f64; PACKET_SIZE]; 3],
+    direction: [[f64; PACKET_SIZE]; 3],
+    inverse_direction: [[f64; PACKET_SIZE]; 3],
+}
+
+impl RayPacket {
+    /// Panics if there are no `rays` or more than `PACKET_SIZE` of them.
+    pub fn new(rays: &[Ray]) -> Self {
+        assert!((1..=PACKET_SIZE).contains(&rays.len()));
+        let len = rays.len();
+        let rays: [Ray; PACKET_SIZE] =
+            std::array::from_fn(|lane| *rays.get(lane).unwrap_or(&rays[0]));
+        let component = |vector: fn(&Ray) -> [f64; 3], axis: usize| {
+            std::array::from_fn(|lane| vector(&rays[lane])[axis])
+        };
+        let origin = |ray: &Ray| ray.origin().coords.into();
+        let direction = |ray: &Ray| ray.direction().into_inner().into();
+        let inverse_direction = |ray: &Ray| (*ray.inverse_direction()).into();
+        RayPacket {
+            origin: std::array::from_fn(|axis| component(origin, axis)),
+            direction: std::array::from_fn(|axis| component(direction, axis)),
+            inverse_direction: std::array::from_fn(|axis| component(inverse_direction, axis)),
+            len,
+            rays,
+        }
+    }
+
+    /// Number of rays in the packet
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Rays of the packet, without the repeated ones.
+    pub fn rays(&self) -> &[Ray] {
+        &self.rays[..self.len]
+    }
+
+    pub fn origin(&self, axis: usize) -> &[f64; PACKET_SIZE] {
+        &self.origin[axis]
+    }
+
+    pub fn direction(&self, axis: usize) -> &[f64; PACKET_SIZE] {
+        &self.direction[axis]
+    }
+
+    pub fn inverse_direction(&self, axis: usize) -> &[f64; PACKET_SIZE] {
+        &self.inverse_direction[axis]
+    }
+}
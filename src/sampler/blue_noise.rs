@@ -0,0 +1,139 @@
+use std::sync::OnceLock;
+
+use nalgebra::Vector2;
+use rand::{rngs::SmallRng, seq::SliceRandom, SeedableRng};
+
+use crate::utils::rng;
+
+use super::{independent::IndependentSampler, SampleContext, Sampler, PRIMES};
+
+/// Values of neighbouring pixels are taken from a blue noise mask, so the error of the first
+/// samples spreads as high frequency noise instead of clumps. Next samples of the pixel move
+/// along the additive recurrence `mask + sample * step`, where step of every dimension
+/// is the fractional part of the square root of a different prime, which covers the domain evenly
+/// without correlating dimensions. Dimensions past `PRIMES` are sampled independently.
+///
+/// Every dimension uses the mask shifted by a random offset, and values get random jitter
+/// inside their rank in the mask, so every value is uniformly distributed on its own.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct BlueNoiseSampler;
+
+impl BlueNoiseSampler {
+    /// Value of the mask in the pixel of the sample, shifted by an offset chosen by `dimension`.
+    fn mask_value(context: &SampleContext, dimension: u32) -> f64 {
+        let offset = rng::hash(&[context.seed, dimension as u64]);
+        let x = (context.pixel_x as usize + offset as usize) % MASK_SIZE;
+        let y = (context.pixel_y as usize + (offset >> 32) as usize) % MASK_SIZE;
+        let rank = blue_noise_mask()[y * MASK_SIZE + x];
+        let jitter = rng::unit_f64(context.pixel_hash(dimension, 0));
+        (rank as f64 + jitter) / (MASK_SIZE * MASK_SIZE) as f64
+    }
+}
+
+impl Sampler for BlueNoiseSampler {
+    fn get_1d(&self, context: &SampleContext, dimension: u32) -> f64 {
+        let Some(prime) = PRIMES.get(dimension as usize) else {
+            return IndependentSampler.get_1d(context, dimension);
+        };
+        let step = (*prime as f64).sqrt().fract();
+        (Self::mask_value(context, dimension) + context.sample as f64 * step).fract()
+    }
+
+    fn get_2d(&self, context: &SampleContext, dimension: u32) -> Vector2<f64> {
+        Vector2::new(
+            self.get_1d(context, dimension),
+            self.get_1d(context, dimension + 1),
+        )
+    }
+}
+
+/// Width and height of the tiled blue noise mask
+const MASK_SIZE: usize = 64;
+
+/// Returns ranks (`[0, MASK_SIZE^2)`) of all mask pixels, row by row. The mask is generated
+/// on the first use.
+fn blue_noise_mask() -> &'static [u16] {
+    static MASK: OnceLock<Vec<u16>> = OnceLock::new();
+    MASK.get_or_init(void_and_cluster)
+}
+
+/// Generates blue noise mask with Ulichney's void-and-cluster method: pixels are ranked
+/// in the order in which they're added to the pattern, each time into the largest void.
+fn void_and_cluster() -> Vec<u16> {
+    const SEED: u64 = 0x5eed;
+    const INITIAL_FRACTION: usize = 10;
+    const SIGMA: f64 = 1.5;
+    let pixel_count = MASK_SIZE * MASK_SIZE;
+
+    // Energy of a point at every toroidal offset from it
+    let kernel: Vec<f64> = (0..pixel_count)
+        .map(|offset| {
+            let distance = |d: usize| d.min(MASK_SIZE - d) as f64;
+            let (dx, dy) = (distance(offset % MASK_SIZE), distance(offset / MASK_SIZE));
+            (-(dx * dx + dy * dy) / (2.0 * SIGMA * SIGMA)).exp()
+        })
+        .collect();
+    let update_energy = |energy: &mut [f64], point: usize, sign: f64| {
+        let (px, py) = (point % MASK_SIZE, point / MASK_SIZE);
+        for (index, value) in energy.iter_mut().enumerate() {
+            let dx = (index % MASK_SIZE + MASK_SIZE - px) % MASK_SIZE;
+            let dy = (index / MASK_SIZE + MASK_SIZE - py) % MASK_SIZE;
+            *value += sign * kernel[dy * MASK_SIZE + dx];
+        }
+    };
+    // Pixel with the highest energy among the set ones, or the lowest among the unset ones
+    let tightest_cluster = |pattern: &[bool], energy: &[f64]| {
+        (0..pixel_count)
+            .filter(|&index| pattern[index])
+            .max_by(|&a, &b| energy[a].total_cmp(&energy[b]))
+            .unwrap()
+    };
+    let largest_void = |pattern: &[bool], energy: &[f64]| {
+        (0..pixel_count)
+            .filter(|&index| !pattern[index])
+            .min_by(|&a, &b| energy[a].total_cmp(&energy[b]))
+            .unwrap()
+    };
+
+    // Random initial pattern, relaxed by moving points from clusters into voids
+    let mut rng = SmallRng::seed_from_u64(SEED);
+    let mut order: Vec<usize> = (0..pixel_count).collect();
+    order.shuffle(&mut rng);
+    let initial_count = pixel_count / INITIAL_FRACTION;
+    let mut pattern = vec![false; pixel_count];
+    let mut energy = vec![0.0; pixel_count];
+    for &point in &order[..initial_count] {
+        pattern[point] = true;
+        update_energy(&mut energy, point, 1.0);
+    }
+    // Relaxation practically always converges quickly, the limit only guards against cycles
+    for _ in 0..pixel_count {
+        let cluster = tightest_cluster(&pattern, &energy);
+        pattern[cluster] = false;
+        update_energy(&mut energy, cluster, -1.0);
+        let void = largest_void(&pattern, &energy);
+        pattern[void] = true;
+        update_energy(&mut energy, void, 1.0);
+        if void == cluster {
+            break;
+        }
+    }
+
+    let mut ranks = vec![0; pixel_count];
+    // Points of the initial pattern are ranked by removing them from the tightest clusters
+    let (mut removed_pattern, mut removed_energy) = (pattern.clone(), energy.clone());
+    for rank in (0..initial_count).rev() {
+        let cluster = tightest_cluster(&removed_pattern, &removed_energy);
+        removed_pattern[cluster] = false;
+        update_energy(&mut removed_energy, cluster, -1.0);
+        ranks[cluster] = rank as u16;
+    }
+    // The rest are ranked by filling the largest voids
+    for rank in initial_count..pixel_count {
+        let void = largest_void(&pattern, &energy);
+        pattern[void] = true;
+        update_energy(&mut energy, void, 1.0);
+        ranks[void] = rank as u16;
+    }
+    ranks
+}
@@ -0,0 +1,44 @@
+use nalgebra::Vector2;
+
+use crate::utils::rng;
+
+use super::{independent::IndependentSampler, SampleContext, Sampler, PRIMES};
+
+/// Halton sequence, every dimension is the radical inverse of the sample index in the next
+/// prime base.
+///
+/// Sequence of every pixel and dimension is shifted by a random offset (Cranley-Patterson rotation),
+/// so pixels aren't correlated. Dimensions past `PRIMES` are sampled independently, as the bases
+/// get too large to cover the domain with usual numbers of samples.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct HaltonSampler;
+
+impl Sampler for HaltonSampler {
+    fn get_1d(&self, context: &SampleContext, dimension: u32) -> f64 {
+        let Some(base) = PRIMES.get(dimension as usize) else {
+            return IndependentSampler.get_1d(context, dimension);
+        };
+        let offset = rng::unit_f64(context.pixel_hash(dimension, 0));
+        (radical_inverse(*base, context.sample) + offset).fract()
+    }
+
+    fn get_2d(&self, context: &SampleContext, dimension: u32) -> Vector2<f64> {
+        Vector2::new(
+            self.get_1d(context, dimension),
+            self.get_1d(context, dimension + 1),
+        )
+    }
+}
+
+/// Mirrors digits of `index` written in `base` around the decimal point.
+fn radical_inverse(base: u32, mut index: u32) -> f64 {
+    let inverse_base = 1.0 / base as f64;
+    let mut digit_weight = inverse_base;
+    let mut result = 0.0;
+    while index > 0 {
+        result += (index % base) as f64 * digit_weight;
+        index /= base;
+        digit_weight *= inverse_base;
+    }
+    result
+}
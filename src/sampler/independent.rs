@@ -0,0 +1,24 @@
+use nalgebra::Vector2;
+use rand::Rng;
+
+use crate::utils::rng;
+
+use super::{SampleContext, Sampler};
+
+/// Every value is drawn independently from the thread-local generator (`rng::rng`),
+/// which the renderer reseeds before every sample.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct IndependentSampler;
+
+impl Sampler for IndependentSampler {
+    fn get_1d(&self, _: &SampleContext, _: u32) -> f64 {
+        rng::rng().random()
+    }
+
+    fn get_2d(&self, _: &SampleContext, _: u32) -> Vector2<f64> {
+        let mut rng = rng::rng();
+        let x = rng.random();
+        let y = rng.random();
+        Vector2::new(x, y)
+    }
+}
@@ -0,0 +1,205 @@
+pub mod blue_noise;
+pub mod halton;
+pub mod independent;
+pub mod sobol;
+pub mod stratified;
+
+use std::cell::RefCell;
+
+use blue_noise::BlueNoiseSampler;
+use halton::HaltonSampler;
+use independent::IndependentSampler;
+use nalgebra::Vector2;
+use sobol::SobolSampler;
+use stratified::StratifiedSampler;
+
+use crate::utils::rng;
+
+/// Primes used as bases (or their square roots as steps) of the low-discrepancy sequences,
+/// one per dimension
+const PRIMES: [u32; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+/// Identifies the camera sample being rendered, samplers derive all its values from it.
+#[derive(Clone, Copy, Default)]
+pub struct SampleContext {
+    pub seed: u64,
+    pub pixel_x: u32,
+    pub pixel_y: u32,
+    /// Index of the sample within the pixel
+    pub sample: u32,
+    pub samples_per_pixel: u32,
+}
+
+impl SampleContext {
+    /// Returns hash of the pixel, `dimension` and `salt`, which stays the same for all samples
+    /// of the pixel.
+    pub fn pixel_hash(&self, dimension: u32, salt: u64) -> u64 {
+        rng::hash(&[
+            self.seed,
+            self.pixel_x as u64,
+            self.pixel_y as u64,
+            dimension as u64,
+            salt,
+        ])
+    }
+
+    /// Returns hash unique for the sample, `dimension` and `salt`.
+    pub fn sample_hash(&self, dimension: u32, salt: u64) -> u64 {
+        rng::hash(&[self.pixel_hash(dimension, salt), self.sample as u64])
+    }
+}
+
+/// Generator of the values in `[0, 1)` range consumed while rendering a camera sample.
+///
+/// Every value is identified by its dimension, which is the number of values requested earlier
+/// in the same sample, so e.g. pixel offsets of all samples of a pixel share the dimensions.
+/// Samplers spread values of the same dimensions evenly over all samples of the pixel,
+/// but every single value must still be uniformly distributed on its own, so any number
+/// of samples (e.g. with adaptive sampling) gives an unbiased image.
+pub trait Sampler {
+    fn get_1d(&self, context: &SampleContext, dimension: u32) -> f64;
+
+    /// Returns two values, which take dimensions `dimension` and `dimension + 1`.
+    fn get_2d(&self, context: &SampleContext, dimension: u32) -> Vector2<f64>;
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SamplerType {
+    Independent(IndependentSampler),
+    Stratified(StratifiedSampler),
+    Halton(HaltonSampler),
+    Sobol(SobolSampler),
+    BlueNoise(BlueNoiseSampler),
+}
+
+impl SamplerType {
+    /// All samplers, index in this array identifies the sampler in checkpoint files.
+    pub const ALL: [SamplerType; 5] = [
+        SamplerType::Independent(IndependentSampler),
+        SamplerType::Stratified(StratifiedSampler),
+        SamplerType::Halton(HaltonSampler),
+        SamplerType::Sobol(SobolSampler),
+        SamplerType::BlueNoise(BlueNoiseSampler),
+    ];
+}
+
+impl Sampler for SamplerType {
+    fn get_1d(&self, context: &SampleContext, dimension: u32) -> f64 {
+        match self {
+            SamplerType::Independent(sampler) => sampler.get_1d(context, dimension),
+            SamplerType::Stratified(sampler) => sampler.get_1d(context, dimension),
+            SamplerType::Halton(sampler) => sampler.get_1d(context, dimension),
+            SamplerType::Sobol(sampler) => sampler.get_1d(context, dimension),
+            SamplerType::BlueNoise(sampler) => sampler.get_1d(context, dimension),
+        }
+    }
+
+    fn get_2d(&self, context: &SampleContext, dimension: u32) -> Vector2<f64> {
+        match self {
+            SamplerType::Independent(sampler) => sampler.get_2d(context, dimension),
+            SamplerType::Stratified(sampler) => sampler.get_2d(context, dimension),
+            SamplerType::Halton(sampler) => sampler.get_2d(context, dimension),
+            SamplerType::Sobol(sampler) => sampler.get_2d(context, dimension),
+            SamplerType::BlueNoise(sampler) => sampler.get_2d(context, dimension),
+        }
+    }
+}
+
+impl Default for SamplerType {
+    fn default() -> Self {
+        IndependentSampler.into()
+    }
+}
+
+impl From<IndependentSampler> for SamplerType {
+    fn from(value: IndependentSampler) -> Self {
+        SamplerType::Independent(value)
+    }
+}
+
+impl From<StratifiedSampler> for SamplerType {
+    fn from(value: StratifiedSampler) -> Self {
+        SamplerType::Stratified(value)
+    }
+}
+
+impl From<HaltonSampler> for SamplerType {
+    fn from(value: HaltonSampler) -> Self {
+        SamplerType::Halton(value)
+    }
+}
+
+impl From<SobolSampler> for SamplerType {
+    fn from(value: SobolSampler) -> Self {
+        SamplerType::Sobol(value)
+    }
+}
+
+impl From<BlueNoiseSampler> for SamplerType {
+    fn from(value: BlueNoiseSampler) -> Self {
+        SamplerType::BlueNoise(value)
+    }
+}
+
+/// Sample rendered by the thread, together with the next dimension to use.
+#[derive(Clone, Copy, Default)]
+struct CurrentSample {
+    sampler: SamplerType,
+    context: SampleContext,
+    dimension: u32,
+}
+
+thread_local! {
+    static CURRENT_SAMPLE: RefCell<CurrentSample> = RefCell::new(CurrentSample::default());
+}
+
+/// Starts sample `context` on this thread, `get_1d` and `get_2d` take values from `sampler`
+/// until the next sample is started.
+///
+/// Like `rng::rng`, sampler is thread-local, so values don't have to be passed through every
+/// material and object. Threads which never started a sample use independent random values.
+pub fn start_sample(sampler: SamplerType, context: SampleContext) {
+    CURRENT_SAMPLE.with_borrow_mut(|current| {
+        *current = CurrentSample {
+            sampler,
+            context,
+            dimension: 0,
+        }
+    });
+}
+
+/// State of the current sample, see `save`.
+#[derive(Clone, Copy)]
+pub struct SampleState(CurrentSample);
+
+/// Returns state of the current sample, so that it can be interrupted by other samples
+/// and continued later with `restore`.
+pub fn save() -> SampleState {
+    CURRENT_SAMPLE.with_borrow(|current| SampleState(*current))
+}
+
+/// Continues the sample saved with `save`.
+pub fn restore(state: SampleState) {
+    CURRENT_SAMPLE.with_borrow_mut(|current| *current = state.0);
+}
+
+/// Returns the next value of the current sample.
+pub fn get_1d() -> f64 {
+    CURRENT_SAMPLE.with_borrow_mut(|current| {
+        let value = current.sampler.get_1d(&current.context, current.dimension);
+        current.dimension += 1;
+        value
+    })
+}
+
+/// Returns the next pair of values of the current sample.
+pub fn get_2d() -> Vector2<f64> {
+    CURRENT_SAMPLE.with_borrow_mut(|current| {
+        let value = current.sampler.get_2d(&current.context, current.dimension);
+        current.dimension += 2;
+        value
+    })
+}
@@ -0,0 +1,86 @@
+use nalgebra::Vector2;
+
+use super::{SampleContext, Sampler};
+
+/// Owen-scrambled Sobol sequence with padding (Burley, "Practical Hash-based Owen Scrambling").
+///
+/// Every 1D and 2D value comes from the first one or two dimensions of the Sobol sequence,
+/// which are well distributed together, with sample indices shuffled differently for every
+/// pixel and dimension, so values of different dimensions aren't correlated. Scrambling
+/// keeps the stratification of the sequence while making every value uniformly random.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct SobolSampler;
+
+impl SobolSampler {
+    /// Salts of the hashes used for shuffling indices and scrambling both coordinates
+    const INDEX_SALT: u64 = 0;
+    const X_SALT: u64 = 1;
+    const Y_SALT: u64 = 2;
+
+    fn shuffled_index(context: &SampleContext, dimension: u32) -> u32 {
+        nested_uniform_scramble(
+            context.sample,
+            context.pixel_hash(dimension, Self::INDEX_SALT) as u32,
+        )
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn get_1d(&self, context: &SampleContext, dimension: u32) -> f64 {
+        let index = Self::shuffled_index(context, dimension);
+        let x = nested_uniform_scramble(
+            index.reverse_bits(),
+            context.pixel_hash(dimension, Self::X_SALT) as u32,
+        );
+        to_unit_f64(x)
+    }
+
+    fn get_2d(&self, context: &SampleContext, dimension: u32) -> Vector2<f64> {
+        let index = Self::shuffled_index(context, dimension);
+        let x = nested_uniform_scramble(
+            index.reverse_bits(),
+            context.pixel_hash(dimension, Self::X_SALT) as u32,
+        );
+        let y = nested_uniform_scramble(
+            sobol_second_dimension(index),
+            context.pixel_hash(dimension, Self::Y_SALT) as u32,
+        );
+        Vector2::new(to_unit_f64(x), to_unit_f64(y))
+    }
+}
+
+/// Second dimension of the Sobol sequence (the first one is the van der Corput sequence,
+/// which is just index with reversed bits), as a 32-bit fixed point fraction.
+fn sobol_second_dimension(index: u32) -> u32 {
+    let mut direction = 1 << 31;
+    let mut result = 0;
+    let mut index = index;
+    while index > 0 {
+        if index & 1 == 1 {
+            result ^= direction;
+        }
+        direction ^= direction >> 1;
+        index >>= 1;
+    }
+    result
+}
+
+/// Owen scrambling of the 32-bit fixed point fraction `x`, every bit is flipped depending
+/// on the bits above it.
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+/// Hash in which every bit depends only on the bits below it.
+fn laine_karras_permutation(x: u32, seed: u32) -> u32 {
+    let mut x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50b47c);
+    x ^= x.wrapping_mul(0xb82f1e52);
+    x ^= x.wrapping_mul(0xc7afe638);
+    x ^= x.wrapping_mul(0x8d22f6e6);
+    x
+}
+
+fn to_unit_f64(x: u32) -> f64 {
+    x as f64 / (1u64 << 32) as f64
+}
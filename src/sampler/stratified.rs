@@ -0,0 +1,80 @@
+use nalgebra::Vector2;
+
+use crate::utils::rng;
+
+use super::{SampleContext, Sampler};
+
+/// Jittered sampling: the domain of every dimension is split into a stratum per sample
+/// (a grid of them for 2D values) and every sample takes a random point inside a different one.
+///
+/// Strata are assigned to samples in random order, different in every pixel and dimension,
+/// so every value is still uniformly distributed on its own.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct StratifiedSampler;
+
+impl Sampler for StratifiedSampler {
+    fn get_1d(&self, context: &SampleContext, dimension: u32) -> f64 {
+        let strata = context.samples_per_pixel.max(1);
+        let stratum = permutation_element(
+            context.sample,
+            strata,
+            context.pixel_hash(dimension, 0) as u32,
+        );
+        let jitter = rng::unit_f64(context.sample_hash(dimension, 0));
+        (stratum as f64 + jitter) / strata as f64
+    }
+
+    fn get_2d(&self, context: &SampleContext, dimension: u32) -> Vector2<f64> {
+        // Grid may have more cells than there are samples, some of them stay empty then
+        let columns = (context.samples_per_pixel.max(1) as f64).sqrt().ceil() as u32;
+        let rows = context.samples_per_pixel.max(1).div_ceil(columns);
+        let stratum = permutation_element(
+            context.sample,
+            columns * rows,
+            context.pixel_hash(dimension, 0) as u32,
+        );
+        let jitter_x = rng::unit_f64(context.sample_hash(dimension, 0));
+        let jitter_y = rng::unit_f64(context.sample_hash(dimension, 1));
+        Vector2::new(
+            ((stratum % columns) as f64 + jitter_x) / columns as f64,
+            ((stratum / columns) as f64 + jitter_y) / rows as f64,
+        )
+    }
+}
+
+/// Returns `index`-th element of the random permutation of `[0, length)` chosen by `seed`,
+/// without generating the whole permutation (Kensler, "Correlated Multi-Jittered Sampling").
+pub fn permutation_element(index: u32, length: u32, seed: u32) -> u32 {
+    let mut mask = length.wrapping_sub(1);
+    mask |= mask >> 1;
+    mask |= mask >> 2;
+    mask |= mask >> 4;
+    mask |= mask >> 8;
+    mask |= mask >> 16;
+
+    let mut i = index;
+    loop {
+        i ^= seed;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= seed >> 16;
+        i ^= (i & mask) >> 4;
+        i ^= seed >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= seed >> 23;
+        i ^= (i & mask) >> 1;
+        i = i.wrapping_mul(1 | seed >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & mask) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & mask) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & mask) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= mask;
+        i ^= i >> 5;
+        if i < length {
+            break;
+        }
+    }
+    (i.wrapping_add(seed)) % length
+}
@@ -1,11 +1,22 @@
+pub mod obj_loader;
+pub mod scene_description;
+pub mod scene_exporter;
 pub mod scene_generator;
+pub mod scene_loader;
 
+use std::{sync::Arc, time::Instant};
+
+use log::info;
 use rgb::Rgb;
 use typed_builder::TypedBuilder;
 
 use crate::{
-    core::bvh::BvhTree, core::camera::Camera, core::rendered_image::RenderedImage,
+    core::bvh::BvhTree,
+    core::camera::{Camera, CameraOverrides, RenderSettings},
+    core::film::Film,
+    core::rendered_image::RenderedImage,
     material::MaterialType,
+    object::{hittable_objects_list::HittableObjectsList, light::LightList, HittableObjectType},
 };
 
 pub struct Scene {
@@ -26,22 +37,94 @@ impl Scene {
     pub fn render(&self) -> RenderedImage {
         self.camera.render(&self.content, &self.options)
     }
+
+    /// Renders scene with `settings`, see `Camera::render_with`.
+    pub fn render_with<F>(
+        &self,
+        settings: &RenderSettings,
+        resume_from: Option<Film>,
+        on_pass: F,
+    ) -> Film
+    where
+        F: FnMut(&Film),
+    {
+        self.camera
+            .render_with(&self.content, &self.options, settings, resume_from, on_pass)
+    }
+
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    pub fn override_camera(&mut self, overrides: &CameraOverrides) {
+        self.camera = self.camera.with_overrides(overrides);
+    }
+
+    pub fn content(&self) -> &SceneContent {
+        &self.content
+    }
+
+    pub fn options(&self) -> &SceneOptions {
+        &self.options
+    }
 }
 
 pub struct SceneContent {
     materials: Vec<MaterialType>,
-    bvh: BvhTree,
+    /// Objects from which `bvh` was built, kept so the scene can be exported.
+    /// They're shared with `bvh`, which holds items of lists on their own.
+    objects: Vec<Arc<HittableObjectType>>,
+    bvh: BvhTree<Arc<HittableObjectType>>,
+    /// Emissive objects, which are sampled directly
+    lights: LightList,
 }
 
 impl SceneContent {
-    pub fn new(materials: Vec<MaterialType>, bvh: BvhTree) -> Self {
-        SceneContent { materials, bvh }
+    /// Builds BVH of the `objects` with leaves of at most `max_leaf_size` objects.
+    /// Meshes keep their own trees.
+    pub fn new(
+        materials: Vec<MaterialType>,
+        objects: Vec<HittableObjectType>,
+        max_leaf_size: usize,
+    ) -> Self {
+        let objects: Vec<_> = objects.into_iter().map(Arc::new).collect();
+        let bvh = Self::build_bvh(&objects, max_leaf_size);
+        let lights = LightList::new(&objects, &materials);
+        SceneContent {
+            materials,
+            objects,
+            bvh,
+            lights,
+        }
+    }
+
+    fn build_bvh(
+        objects: &[Arc<HittableObjectType>],
+        max_leaf_size: usize,
+    ) -> BvhTree<Arc<HittableObjectType>> {
+        let start = Instant::now();
+        let objects = HittableObjectsList::flatten_hittable_objects_list(objects);
+        let bvh = BvhTree::new(objects, max_leaf_size);
+        info!("Built BVH in {:.2?}: {}", start.elapsed(), bvh.stats());
+        bvh
+    }
+
+    pub fn materials(&self) -> &[MaterialType] {
+        &self.materials
+    }
+
+    pub fn objects(&self) -> &[Arc<HittableObjectType>] {
+        &self.objects
     }
 
-    pub fn bvh(&self) -> &BvhTree {
+    pub fn bvh(&self) -> &BvhTree<Arc<HittableObjectType>> {
         &self.bvh
     }
 
+    pub fn lights(&self) -> &LightList {
+        &self.lights
+    }
+
     pub fn material_by_id(&self, id: usize) -> Option<&MaterialType> {
         if id >= self.materials.len() {
             return None;
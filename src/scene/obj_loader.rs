@@ -0,0 +1,580 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Error, Result};
+use nalgebra::{Point3, Vector2, Vector3};
+use rgb::Rgb;
+
+use crate::{
+    material::{
+        dielectric::Dielectric, diffuse_light::DiffuseLight, lambertian::Lambertian, metal::Metal,
+        MaterialType,
+    },
+    object::{
+        triangle_mesh::{MeshData, TriangleMesh},
+        HittableObjectType,
+    },
+    texture::image_texture::ImageTexture,
+};
+
+/// Describes where materials of the loaded meshes come from.
+#[derive(Clone, Copy)]
+pub enum ObjMaterials {
+    /// Materials are loaded from MTL files, ids are assigned starting at `first_material_id`
+    FromMtl { first_material_id: usize },
+    /// MTL files are ignored and every mesh uses material with `material_id`
+    Override { material_id: usize },
+}
+
+/// Geometry and materials loaded from the OBJ file.
+pub struct ObjModel {
+    /// Materials that should be appended to the scene's materials
+    pub materials: Vec<MaterialType>,
+    /// One `TriangleMesh` per material used in the file
+    pub objects: Vec<HittableObjectType>,
+}
+
+/// Loads OBJ file at `path` together with MTL files it references.
+///
+/// Returned objects assume that their materials are appended to the scene's materials
+/// starting at `first_material_id`. MTL materials are mapped as follows:
+/// - emissive (`Ke`) materials become `DiffuseLight`
+/// - transparent (`d < 1`, `Tr > 0` or `illum` 4, 6, 7) materials become `Dielectric` with `Ni` refraction index
+/// - reflective (`illum` 3, 5) materials become `Metal` with `Ks` albedo and fuzziness derived from `Ns`
+/// - every other material becomes `Lambertian` with `map_Kd` texture or `Kd` color
+///
+/// Faces without material use a default gray `Lambertian`.
+pub fn load_obj<P: AsRef<Path>>(path: P, first_material_id: usize) -> Result<ObjModel> {
+    load_obj_file(path.as_ref(), ObjMaterials::FromMtl { first_material_id })
+}
+
+/// Loads OBJ file at `path` ignoring its MTL files, every mesh uses material with `material_id`.
+pub fn load_obj_with_material<P: AsRef<Path>>(path: P, material_id: usize) -> Result<ObjModel> {
+    load_obj_file(path.as_ref(), ObjMaterials::Override { material_id })
+}
+
+/// Parses OBJ file from `source`, referenced MTL files are resolved against `base_dir`.
+pub fn parse_obj(
+    source: &str,
+    base_dir: &Path,
+    material_mapping: ObjMaterials,
+) -> Result<ObjModel> {
+    let mut positions: Vec<Point3<f64>> = vec![];
+    let mut normals: Vec<Vector3<f64>> = vec![];
+    let mut uvs: Vec<Vector2<f64>> = vec![];
+    let mut mtl_materials: HashMap<String, MaterialType> = HashMap::new();
+    let mut groups: Vec<FaceGroup> = vec![];
+    let mut current_group: Option<usize> = None;
+
+    for (line_id, line) in source.lines().enumerate() {
+        let line_number = line_id + 1;
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let arguments: Vec<&str> = tokens.collect();
+        let error = |message: String| Error::msg(format!("line {}: {}", line_number, message));
+
+        match keyword {
+            "v" => {
+                let [x, y, z] = parse_floats::<3>(&arguments, "vertex").map_err(error)?;
+                positions.push(Point3::new(x, y, z));
+            }
+            "vn" => {
+                let [x, y, z] = parse_floats::<3>(&arguments, "normal").map_err(error)?;
+                let normal = Vector3::new(x, y, z);
+                if normal.norm_squared() == 0.0 {
+                    return Err(error("normal cannot be a zero vector".to_string()));
+                }
+                normals.push(normal);
+            }
+            "vt" => {
+                let [u, v] = parse_floats::<2>(&arguments, "texture coordinate").map_err(error)?;
+                uvs.push(Vector2::new(u, v));
+            }
+            "f" => {
+                if arguments.len() < 3 {
+                    return Err(error("face must have at least 3 vertices".to_string()));
+                }
+                let vertices = arguments
+                    .iter()
+                    .map(|vertex| {
+                        parse_face_vertex(vertex, positions.len(), uvs.len(), normals.len())
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(error)?;
+                let group_id = *current_group.get_or_insert_with(|| {
+                    groups.push(FaceGroup::new(None));
+                    groups.len() - 1
+                });
+                // Polygons are triangulated as a fan around the first vertex
+                for i in 1..vertices.len() - 1 {
+                    groups[group_id]
+                        .faces
+                        .push([vertices[0], vertices[i], vertices[i + 1]]);
+                }
+            }
+            "usemtl" => {
+                let name = arguments.join(" ");
+                if name.is_empty() {
+                    return Err(error("missing material name".to_string()));
+                }
+                let existing = groups
+                    .iter()
+                    .position(|group| group.material.as_ref() == Some(&name));
+                current_group = Some(existing.unwrap_or_else(|| {
+                    groups.push(FaceGroup::new(Some(name)));
+                    groups.len() - 1
+                }));
+            }
+            "mtllib" if matches!(material_mapping, ObjMaterials::FromMtl { .. }) => {
+                let file_name = arguments.join(" ");
+                if file_name.is_empty() {
+                    return Err(error("missing mtl file name".to_string()));
+                }
+                let mtl_path = base_dir.join(file_name);
+                let loaded = load_mtl(&mtl_path)
+                    .with_context(|| format!("line {}: cannot load mtl file", line_number))?;
+                mtl_materials.extend(loaded);
+            }
+            // Object names, groups, smoothing groups and other statements don't affect rendering
+            _ => {}
+        }
+    }
+
+    let mut materials = vec![];
+    let mut objects = vec![];
+    for group in groups.into_iter().filter(|group| !group.faces.is_empty()) {
+        let material_id = match material_mapping {
+            ObjMaterials::FromMtl { first_material_id } => {
+                let material = match &group.material {
+                    Some(name) => mtl_materials
+                        .get(name)
+                        .cloned()
+                        .ok_or_else(|| Error::msg(format!("unknown material '{}'", name)))?,
+                    None => Lambertian::from(Rgb::new(0.8, 0.8, 0.8)).into(),
+                };
+                materials.push(material);
+                first_material_id + materials.len() - 1
+            }
+            ObjMaterials::Override { material_id } => material_id,
+        };
+        let mesh = group.into_mesh_data(&positions, &normals, &uvs);
+        objects.push(TriangleMesh::new(mesh, material_id).into());
+    }
+
+    if objects.is_empty() {
+        bail!("obj file doesn't contain any faces");
+    }
+
+    Ok(ObjModel { materials, objects })
+}
+
+fn load_obj_file(path: &Path, material_mapping: ObjMaterials) -> Result<ObjModel> {
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("cannot read obj file '{}'", path.display()))?;
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    parse_obj(&source, base_dir, material_mapping)
+        .with_context(|| format!("invalid obj file '{}'", path.display()))
+}
+
+/// Indices (counted from 0) of position, texture coordinate and normal of a single face vertex.
+type FaceVertex = (usize, Option<usize>, Option<usize>);
+
+/// Faces sharing the same material.
+struct FaceGroup {
+    material: Option<String>,
+    faces: Vec<[FaceVertex; 3]>,
+}
+
+impl FaceGroup {
+    fn new(material: Option<String>) -> Self {
+        Self {
+            material,
+            faces: vec![],
+        }
+    }
+
+    /// OBJ faces index positions, normals and texture coordinates separately, so every
+    /// distinct combination becomes separate vertex of the mesh. Normals and texture coordinates
+    /// are kept only if every vertex of the group has them.
+    fn into_mesh_data(
+        self,
+        positions: &[Point3<f64>],
+        normals: &[Vector3<f64>],
+        uvs: &[Vector2<f64>],
+    ) -> MeshData {
+        let vertices_iter = || self.faces.iter().flatten();
+        let has_normals = vertices_iter().all(|(_, _, normal)| normal.is_some());
+        let has_uvs = vertices_iter().all(|(_, uv, _)| uv.is_some());
+
+        let mut vertex_ids: HashMap<FaceVertex, usize> = HashMap::new();
+        let mut mesh_positions = vec![];
+        let mut mesh_normals = vec![];
+        let mut mesh_uvs = vec![];
+        let faces = self
+            .faces
+            .iter()
+            .map(|face| {
+                face.map(|vertex| {
+                    let (position, uv, normal) = vertex;
+                    *vertex_ids.entry(vertex).or_insert_with(|| {
+                        mesh_positions.push(positions[position]);
+                        if has_normals {
+                            mesh_normals.push(normals[normal.unwrap()]);
+                        }
+                        if has_uvs {
+                            mesh_uvs.push(uvs[uv.unwrap()]);
+                        }
+                        mesh_positions.len() - 1
+                    })
+                })
+            })
+            .collect();
+
+        MeshData::new(
+            mesh_positions,
+            has_normals.then_some(mesh_normals),
+            has_uvs.then_some(mesh_uvs),
+            faces,
+        )
+    }
+}
+
+fn parse_floats<const N: usize>(arguments: &[&str], name: &str) -> Result<[f64; N], String> {
+    // Additional values (like `w` component) are allowed, but ignored
+    if arguments.len() < N {
+        return Err(format!("{} must have at least {} values", name, N));
+    }
+    let mut values = [0.0; N];
+    for (value, argument) in values.iter_mut().zip(arguments) {
+        *value = argument
+            .parse()
+            .map_err(|_| format!("invalid {} value '{}'", name, argument))?;
+    }
+    Ok(values)
+}
+
+/// Parses single face vertex in one of the formats: `v`, `v/vt`, `v//vn` or `v/vt/vn`.
+/// Negative indices are relative to the end of the already defined elements.
+fn parse_face_vertex(
+    vertex: &str,
+    positions_count: usize,
+    uvs_count: usize,
+    normals_count: usize,
+) -> Result<FaceVertex, String> {
+    let mut parts = vertex.split('/');
+    let position = parts.next().unwrap_or_default();
+    let uv = parts.next().filter(|part| !part.is_empty());
+    let normal = parts.next().filter(|part| !part.is_empty());
+    if parts.next().is_some() {
+        return Err(format!("malformed face vertex '{}'", vertex));
+    }
+
+    let position = resolve_index(position, positions_count, "vertex")?;
+    let uv = uv
+        .map(|uv| resolve_index(uv, uvs_count, "texture coordinate"))
+        .transpose()?;
+    let normal = normal
+        .map(|normal| resolve_index(normal, normals_count, "normal"))
+        .transpose()?;
+    Ok((position, uv, normal))
+}
+
+fn resolve_index(index: &str, count: usize, name: &str) -> Result<usize, String> {
+    let parsed: i64 = index
+        .parse()
+        .map_err(|_| format!("invalid {} index '{}'", name, index))?;
+    let resolved = if parsed > 0 {
+        parsed - 1
+    } else {
+        count as i64 + parsed
+    };
+    if parsed == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(format!("{} index {} is out of range", name, parsed));
+    }
+    Ok(resolved as usize)
+}
+
+/// Parameters of a single MTL material, fields are named after MTL statements.
+struct MtlMaterial {
+    kd: [f64; 3],
+    ks: [f64; 3],
+    ke: [f64; 3],
+    ns: f64,
+    ni: f64,
+    dissolve: f64,
+    illum: u32,
+    map_kd: Option<PathBuf>,
+    /// Line on which material is defined, used for error messages
+    line_number: usize,
+}
+
+impl MtlMaterial {
+    fn new(line_number: usize) -> Self {
+        Self {
+            kd: [0.8, 0.8, 0.8],
+            ks: [0.0, 0.0, 0.0],
+            ke: [0.0, 0.0, 0.0],
+            ns: 0.0,
+            ni: 1.5,
+            dissolve: 1.0,
+            illum: 2,
+            map_kd: None,
+            line_number,
+        }
+    }
+
+    fn into_material(self) -> Result<MaterialType> {
+        if self.ke.iter().any(|&c| c > 0.0) {
+            return Ok(DiffuseLight::from(Rgb::from(self.ke)).into());
+        }
+
+        if self.dissolve < 1.0 || matches!(self.illum, 4 | 6 | 7) {
+            return Ok(Dielectric::new(self.ni).into());
+        }
+
+        if matches!(self.illum, 3 | 5) {
+            // `Ns` is specular exponent in range [0, 1000], the higher it is the sharper reflections are
+            let fuzziness = 1.0 - (self.ns / 1000.0).clamp(0.0, 1.0).sqrt();
+            return Ok(Metal::new(Rgb::from(self.ks), fuzziness).into());
+        }
+
+        match self.map_kd {
+            Some(path) => {
+                let texture = ImageTexture::new(&path).with_context(|| {
+                    format!(
+                        "line {}: cannot load texture '{}'",
+                        self.line_number,
+                        path.display()
+                    )
+                })?;
+                Ok(Lambertian::new(texture.into()).into())
+            }
+            None => Ok(Lambertian::from(Rgb::from(self.kd)).into()),
+        }
+    }
+}
+
+fn load_mtl(path: &Path) -> Result<HashMap<String, MaterialType>> {
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("cannot read mtl file '{}'", path.display()))?;
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    parse_mtl(&source, base_dir).with_context(|| format!("invalid mtl file '{}'", path.display()))
+}
+
+fn parse_mtl(source: &str, base_dir: &Path) -> Result<HashMap<String, MaterialType>> {
+    let mut parsed: Vec<(String, MtlMaterial)> = vec![];
+
+    for (line_id, line) in source.lines().enumerate() {
+        let line_number = line_id + 1;
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let arguments: Vec<&str> = tokens.collect();
+        let error = |message: String| Error::msg(format!("line {}: {}", line_number, message));
+
+        if keyword == "newmtl" {
+            let name = arguments.join(" ");
+            if name.is_empty() {
+                return Err(error("missing material name".to_string()));
+            }
+            parsed.push((name, MtlMaterial::new(line_number)));
+            continue;
+        }
+
+        let Some((_, material)) = parsed.last_mut() else {
+            // Statements before the first `newmtl` don't belong to any material
+            continue;
+        };
+        match keyword {
+            "Kd" => material.kd = parse_floats::<3>(&arguments, "Kd").map_err(error)?,
+            "Ks" => material.ks = parse_floats::<3>(&arguments, "Ks").map_err(error)?,
+            "Ke" => material.ke = parse_floats::<3>(&arguments, "Ke").map_err(error)?,
+            "Ns" => [material.ns] = parse_floats::<1>(&arguments, "Ns").map_err(error)?,
+            "Ni" => {
+                [material.ni] = parse_floats::<1>(&arguments, "Ni").map_err(error)?;
+                if material.ni <= 0.0 {
+                    return Err(error("Ni must be positive".to_string()));
+                }
+            }
+            "d" => [material.dissolve] = parse_floats::<1>(&arguments, "d").map_err(error)?,
+            "Tr" => {
+                let [transparency] = parse_floats::<1>(&arguments, "Tr").map_err(error)?;
+                material.dissolve = 1.0 - transparency;
+            }
+            "illum" => {
+                material.illum = arguments
+                    .first()
+                    .and_then(|illum| illum.parse().ok())
+                    .ok_or_else(|| error("invalid illum value".to_string()))?
+            }
+            "map_Kd" => {
+                // Texture options (e.g. `-s 1 1 1`) are not supported, file name is always the last argument
+                let file_name = arguments
+                    .last()
+                    .ok_or_else(|| error("missing map_Kd file name".to_string()))?;
+                material.map_kd = Some(base_dir.join(file_name));
+            }
+            _ => {}
+        }
+    }
+
+    parsed
+        .into_iter()
+        .map(|(name, material)| Ok((name, material.into_material()?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Result<ObjModel> {
+        parse_obj(
+            source,
+            Path::new("."),
+            ObjMaterials::Override { material_id: 0 },
+        )
+    }
+
+    fn only_mesh(model: &ObjModel) -> &MeshData {
+        match &model.objects[..] {
+            [HittableObjectType::TriangleMesh(mesh)] => mesh.mesh(),
+            _ => panic!("expected a single triangle mesh"),
+        }
+    }
+
+    fn face_positions(mesh: &MeshData) -> Vec<[Point3<f64>; 3]> {
+        mesh.faces()
+            .iter()
+            .map(|face| face.map(|id| mesh.vertices()[id]))
+            .collect()
+    }
+
+    #[test]
+    fn negative_indices_are_relative_to_defined_elements() {
+        let source = "\
+v 0 0 0
+v 1 0 0
+v 0 1 0
+f -3 -2 -1
+v 1 1 0
+f 2 -1 3
+";
+        let model = parse(source).unwrap();
+        let faces = face_positions(only_mesh(&model));
+        assert_eq!(
+            faces,
+            [
+                [
+                    Point3::new(0.0, 0.0, 0.0),
+                    Point3::new(1.0, 0.0, 0.0),
+                    Point3::new(0.0, 1.0, 0.0),
+                ],
+                [
+                    Point3::new(1.0, 0.0, 0.0),
+                    Point3::new(1.0, 1.0, 0.0),
+                    Point3::new(0.0, 1.0, 0.0),
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn quads_are_fanned_into_two_triangles() {
+        let source = "\
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+f 1 2 3 4
+";
+        let model = parse(source).unwrap();
+        let faces = face_positions(only_mesh(&model));
+        let [a, b, c, d] = [
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(1.0, 1.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+        ];
+        assert_eq!(faces, [[a, b, c], [a, c, d]]);
+    }
+
+    #[test]
+    fn vertices_with_normals_but_without_uvs_are_parsed() {
+        let source = "\
+v 0 0 0
+v 1 0 0
+v 0 1 0
+vn 0 0 2
+f 1//1 2//1 3//1
+";
+        let model = parse(source).unwrap();
+        let mesh = only_mesh(&model);
+        assert!(mesh.uvs().is_none());
+        let normals = mesh.normals().expect("mesh should have normals");
+        assert_eq!(normals.len(), 3);
+        for normal in normals {
+            assert_eq!(normal.into_inner(), Vector3::new(0.0, 0.0, 1.0));
+        }
+    }
+
+    #[test]
+    fn out_of_range_indices_are_reported_with_line_number() {
+        for (face, message) in [
+            ("f 1 2 4", "line 5: vertex index 4 is out of range"),
+            ("f 1 2 -4", "line 5: vertex index -4 is out of range"),
+            ("f 1//2 2//1 3//1", "line 5: normal index 2 is out of range"),
+        ] {
+            let source = format!("v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\n{face}\n");
+            let error = parse(&source).err().expect("parsing should fail");
+            assert_eq!(format!("{error:#}"), message);
+        }
+    }
+
+    #[test]
+    fn mtl_materials_are_mapped_by_emission_dissolve_and_illum() {
+        let source = "\
+newmtl light
+Ke 4 4 4
+d 0.5
+newmtl transparent
+d 0.5
+Ni 1.3
+newmtl glass
+illum 6
+newmtl mirror
+illum 3
+Ks 0.9 0.9 0.9
+Ns 1000
+newmtl plain
+Kd 0.1 0.2 0.3
+";
+        let materials = parse_mtl(source, Path::new(".")).unwrap();
+        assert_eq!(materials.len(), 5);
+        assert!(matches!(materials["light"], MaterialType::DiffuseLight(_)));
+        match &materials["transparent"] {
+            MaterialType::Dielectric(dielectric) => assert_eq!(dielectric.refraction_index(), 1.3),
+            _ => panic!("transparent material should be dielectric"),
+        }
+        match &materials["glass"] {
+            MaterialType::Dielectric(dielectric) => assert_eq!(dielectric.refraction_index(), 1.5),
+            _ => panic!("illum 6 material should be dielectric"),
+        }
+        match &materials["mirror"] {
+            MaterialType::Metal(metal) => {
+                assert_eq!(*metal.albedo(), Rgb::new(0.9, 0.9, 0.9));
+                assert_eq!(metal.fuzziness(), 0.0);
+            }
+            _ => panic!("illum 3 material should be metal"),
+        }
+        assert!(matches!(materials["plain"], MaterialType::Lambertian(_)));
+    }
+}
@@ -0,0 +1,208 @@
+use serde::{Deserialize, Serialize};
+use toml::Spanned;
+
+use crate::utils::rng;
+
+/// Top level structure of the scene file.
+///
+/// Materials and textures are referenced by their names, objects refer to materials
+/// and materials (or checker textures) refer to textures. Order of materials in the file
+/// is preserved, so `n`-th material gets `material_id` equal to `n`.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SceneDescription {
+    pub camera: Spanned<CameraDescription>,
+    #[serde(default)]
+    pub options: OptionsDescription,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub textures: Vec<Spanned<NamedTextureDescription>>,
+    #[serde(default)]
+    pub materials: Vec<Spanned<NamedMaterialDescription>>,
+    #[serde(default)]
+    pub objects: Vec<Spanned<ObjectDescription>>,
+}
+
+/// Parameters passed to `Camera::builder`, every missing field uses the same default value as the builder.
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CameraDescription {
+    pub width: u32,
+    pub aspect_ratio: f64,
+    pub samples_per_pixel: u32,
+    pub max_bounce_depth: u32,
+    pub vertical_fov_angles: f64,
+    pub center: [f64; 3],
+    pub look_at: [f64; 3],
+    pub relative_up: [f64; 3],
+    pub defocus_angle: f64,
+    pub focus_distance: f64,
+    /// Seed of the random numbers used for sampling and generating noise textures,
+    /// `u32` so it always fits into TOML integers
+    pub seed: u32,
+}
+
+impl Default for CameraDescription {
+    fn default() -> Self {
+        Self {
+            width: 400,
+            aspect_ratio: 16.0 / 9.0,
+            samples_per_pixel: 100,
+            max_bounce_depth: 10,
+            vertical_fov_angles: 90.0,
+            center: [0.0, 0.0, 0.0],
+            look_at: [0.0, 0.0, -1.0],
+            relative_up: [0.0, 1.0, 0.0],
+            defocus_angle: 0.0,
+            focus_distance: 10.0,
+            seed: rng::DEFAULT_SEED,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OptionsDescription {
+    pub background: [f64; 3],
+}
+
+impl Default for OptionsDescription {
+    fn default() -> Self {
+        Self {
+            background: [0.7, 0.8, 1.0],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct NamedTextureDescription {
+    pub name: String,
+    #[serde(flatten)]
+    pub texture: TextureDescription,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum TextureDescription {
+    SolidColor {
+        color: [f64; 3],
+    },
+    /// `path` is relative to the directory containing the scene file
+    Image {
+        path: String,
+    },
+    Noise {
+        scale: f64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        turbulance_depth: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        turbulance_factor: Option<f64>,
+        /// Seed of the noise, drawn from the camera seed when missing
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        seed: Option<u32>,
+    },
+    /// `even` and `odd` must be names of textures that are not checker textures themselves
+    Checker {
+        scale: f64,
+        even: String,
+        odd: String,
+    },
+}
+
+/// Texture used by material can be either plain color or name of texture defined in the scene file.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TextureReference {
+    Color([f64; 3]),
+    Named(String),
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct NamedMaterialDescription {
+    pub name: String,
+    #[serde(flatten)]
+    pub material: MaterialDescription,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum MaterialDescription {
+    Lambertian { texture: TextureReference },
+    Metal { albedo: [f64; 3], fuzziness: f64 },
+    Dielectric { refraction_index: f64 },
+    DiffuseLight { texture: TextureReference },
+    Isotropic { texture: TextureReference },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ObjectDescription {
+    Sphere {
+        center: [f64; 3],
+        radius: f64,
+        material: String,
+    },
+    MovingSphere {
+        from: [f64; 3],
+        to: [f64; 3],
+        radius: f64,
+        material: String,
+    },
+    Quad {
+        start: [f64; 3],
+        u: [f64; 3],
+        v: [f64; 3],
+        material: String,
+    },
+    /// `normals` and `uvs` are optional per-vertex normals (used for smooth shading)
+    /// and texture coordinates
+    Triangle {
+        vertices: [[f64; 3]; 3],
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        normals: Option<[[f64; 3]; 3]>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        uvs: Option<[[f64; 2]; 3]>,
+        material: String,
+    },
+    /// Each face is a triple of indices into `vertices`, optional `normals` and `uvs`
+    /// are indexed the same way as `vertices`
+    TriangleMesh {
+        vertices: Vec<[f64; 3]>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        normals: Option<Vec<[f64; 3]>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        uvs: Option<Vec<[f64; 2]>>,
+        faces: Vec<[usize; 3]>,
+        material: String,
+    },
+    /// Meshes loaded from OBJ file at `path` (relative to the scene file). Materials from MTL files
+    /// are appended to the scene's materials, unless `material` is set - then it's used for every mesh.
+    Obj {
+        path: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        material: Option<String>,
+    },
+    /// Box built from 6 quads, `start` and `end` are its opposite vertices
+    Cuboid {
+        start: [f64; 3],
+        end: [f64; 3],
+        material: String,
+    },
+    List {
+        objects: Vec<ObjectDescription>,
+    },
+    Translate {
+        offset: [f64; 3],
+        object: Box<ObjectDescription>,
+    },
+    /// `angle` is in degrees
+    RotateY {
+        angle: f64,
+        object: Box<ObjectDescription>,
+    },
+    /// `material` is the phase function of the medium, usually `isotropic` material
+    ConstantDensityMedium {
+        density: f64,
+        material: String,
+        boundary: Box<ObjectDescription>,
+    },
+}
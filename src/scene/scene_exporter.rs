@@ -0,0 +1,284 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use nalgebra::{Point3, Vector3};
+use rgb::Rgb;
+use toml::Spanned;
+
+use crate::{
+    core::camera::Camera,
+    material::MaterialType,
+    object::HittableObjectType,
+    texture::{NonRecursiveTexture, TextureType},
+};
+
+use super::{
+    scene_description::{
+        CameraDescription, MaterialDescription, NamedMaterialDescription, NamedTextureDescription,
+        ObjectDescription, OptionsDescription, SceneDescription, TextureDescription,
+        TextureReference,
+    },
+    Scene, SceneOptions,
+};
+
+/// Saves `scene` as a scene file at `path`, which can be loaded back with `scene_loader::load_scene`.
+pub fn export_scene<P: AsRef<Path>>(scene: &Scene, path: P) -> Result<()> {
+    let path = path.as_ref();
+    let serialized = scene_to_string(scene)?;
+    fs::write(path, serialized)
+        .with_context(|| format!("cannot write scene file '{}'", path.display()))
+}
+
+/// Serializes `scene` into the scene file format.
+pub fn scene_to_string(scene: &Scene) -> Result<String> {
+    let description = describe_scene(scene);
+    toml::to_string(&description).context("cannot serialize scene")
+}
+
+/// Returns hash of the serialized `scene`, used to check whether checkpoint belongs to the scene.
+///
+/// Number of samples per pixel is not included, so the render can be resumed with more samples.
+/// Seed is stored in checkpoints separately, so it's not included either.
+pub fn scene_hash(scene: &Scene) -> Result<u64> {
+    let mut description = describe_scene(scene);
+    let camera = description.camera.get_mut();
+    camera.samples_per_pixel = 0;
+    camera.seed = 0;
+    let serialized = toml::to_string(&description).context("cannot serialize scene")?;
+
+    // FNV-1a, unlike `DefaultHasher` it's guaranteed to be the same across Rust versions
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    Ok(serialized.bytes().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(PRIME)
+    }))
+}
+
+/// Converts `scene` into its `SceneDescription`.
+///
+/// Materials are named `material_<material_id>`, so their ids are preserved after loading the scene back.
+/// Noise textures keep their seed, so they are generated the same way on load.
+pub fn describe_scene(scene: &Scene) -> SceneDescription {
+    let mut exporter = SceneExporter::default();
+
+    let materials = scene
+        .content()
+        .materials()
+        .iter()
+        .enumerate()
+        .map(|(id, material)| {
+            let material = NamedMaterialDescription {
+                name: material_name(id),
+                material: exporter.describe_material(material),
+            };
+            Spanned::new(0..0, material)
+        })
+        .collect();
+
+    let objects = scene
+        .content()
+        .objects()
+        .iter()
+        .map(|object| Spanned::new(0..0, describe_object(object)))
+        .collect();
+
+    SceneDescription {
+        camera: Spanned::new(0..0, describe_camera(scene.camera())),
+        options: describe_options(scene.options()),
+        textures: exporter.textures,
+        materials,
+        objects,
+    }
+}
+
+#[derive(Default)]
+struct SceneExporter {
+    textures: Vec<Spanned<NamedTextureDescription>>,
+}
+
+impl SceneExporter {
+    fn describe_material(&mut self, material: &MaterialType) -> MaterialDescription {
+        match material {
+            MaterialType::Lambertian(lambertian) => MaterialDescription::Lambertian {
+                texture: self.texture_reference(lambertian.texture()),
+            },
+            MaterialType::Metal(metal) => MaterialDescription::Metal {
+                albedo: color(metal.albedo()),
+                fuzziness: metal.fuzziness(),
+            },
+            MaterialType::Dielectric(dielectric) => MaterialDescription::Dielectric {
+                refraction_index: dielectric.refraction_index(),
+            },
+            MaterialType::DiffuseLight(diffuse_light) => MaterialDescription::DiffuseLight {
+                texture: self.texture_reference(diffuse_light.texture()),
+            },
+            MaterialType::Isotropic(isotropic) => MaterialDescription::Isotropic {
+                texture: self.texture_reference(isotropic.texture()),
+            },
+        }
+    }
+
+    /// Solid colors are written inline, every other texture is added to the list of named textures.
+    fn texture_reference(&mut self, texture: &TextureType) -> TextureReference {
+        match texture {
+            TextureType::NonRecursive(NonRecursiveTexture::SolidColor(solid_color)) => {
+                TextureReference::Color(color(solid_color.albedo()))
+            }
+            TextureType::NonRecursive(non_recursive_texture) => {
+                TextureReference::Named(self.add_non_recursive_texture(non_recursive_texture))
+            }
+            TextureType::CheckerTexture(checker_texture) => {
+                let even = self.add_non_recursive_texture(checker_texture.even_texture());
+                let odd = self.add_non_recursive_texture(checker_texture.odd_texture());
+                let checker = TextureDescription::Checker {
+                    scale: checker_texture.scale(),
+                    even,
+                    odd,
+                };
+                TextureReference::Named(self.add_texture(checker))
+            }
+        }
+    }
+
+    fn add_non_recursive_texture(&mut self, texture: &NonRecursiveTexture) -> String {
+        let description = match texture {
+            NonRecursiveTexture::SolidColor(solid_color) => TextureDescription::SolidColor {
+                color: color(solid_color.albedo()),
+            },
+            NonRecursiveTexture::ImageTexture(image_texture) => {
+                // Scene file paths are relative to the file itself, so absolute path is
+                // the only one that stays valid no matter where the scene is exported.
+                let path = image_texture
+                    .path()
+                    .canonicalize()
+                    .unwrap_or_else(|_| image_texture.path().to_path_buf());
+                TextureDescription::Image {
+                    path: path.to_string_lossy().into_owned(),
+                }
+            }
+            NonRecursiveTexture::NoiseTexture(noise_texture) => TextureDescription::Noise {
+                scale: noise_texture.scale(),
+                turbulance_depth: Some(noise_texture.turbulance_depth()),
+                turbulance_factor: Some(noise_texture.turbulance_factor()),
+                seed: Some(noise_texture.seed()),
+            },
+        };
+        self.add_texture(description)
+    }
+
+    fn add_texture(&mut self, texture: TextureDescription) -> String {
+        let name = format!("texture_{}", self.textures.len());
+        let named = NamedTextureDescription {
+            name: name.clone(),
+            texture,
+        };
+        self.textures.push(Spanned::new(0..0, named));
+        name
+    }
+}
+
+fn describe_camera(camera: &Camera) -> CameraDescription {
+    CameraDescription {
+        width: camera.width(),
+        aspect_ratio: camera.aspect_ratio(),
+        samples_per_pixel: camera.samples_per_pixel(),
+        max_bounce_depth: camera.max_bounce_depth(),
+        vertical_fov_angles: camera.vertical_fov_angles(),
+        center: point(camera.center()),
+        look_at: point(camera.look_at()),
+        relative_up: vector(camera.relative_up()),
+        defocus_angle: camera.defocus_angle(),
+        focus_distance: camera.focus_distance(),
+        seed: camera.seed(),
+    }
+}
+
+fn describe_options(options: &SceneOptions) -> OptionsDescription {
+    OptionsDescription {
+        background: color(options.background()),
+    }
+}
+
+fn describe_object(object: &HittableObjectType) -> ObjectDescription {
+    match object {
+        HittableObjectType::Sphere(sphere) => ObjectDescription::Sphere {
+            center: point(sphere.center()),
+            radius: sphere.radius(),
+            material: material_name(sphere.material_id()),
+        },
+        HittableObjectType::MovingSphere(moving_sphere) => ObjectDescription::MovingSphere {
+            from: point(moving_sphere.from()),
+            to: point(&moving_sphere.to()),
+            radius: moving_sphere.radius(),
+            material: material_name(moving_sphere.material_id()),
+        },
+        HittableObjectType::Quad(quad) => ObjectDescription::Quad {
+            start: point(quad.start()),
+            u: vector(quad.u()),
+            v: vector(quad.v()),
+            material: material_name(quad.material_id()),
+        },
+        HittableObjectType::Triangle(triangle) => ObjectDescription::Triangle {
+            vertices: triangle.vertices().map(|vertex| point(&vertex)),
+            normals: triangle
+                .normals()
+                .map(|normals| normals.map(|normal| vector(&normal))),
+            uvs: triangle.uvs().map(|uvs| uvs.map(|uv| [uv.x, uv.y])),
+            material: material_name(triangle.material_id()),
+        },
+        HittableObjectType::TriangleMesh(triangle_mesh) => {
+            let mesh = triangle_mesh.mesh();
+            ObjectDescription::TriangleMesh {
+                vertices: mesh.vertices().iter().map(point).collect(),
+                normals: mesh
+                    .normals()
+                    .map(|normals| normals.iter().map(|normal| vector(normal)).collect()),
+                uvs: mesh
+                    .uvs()
+                    .map(|uvs| uvs.iter().map(|uv| [uv.x, uv.y]).collect()),
+                faces: mesh.faces().to_vec(),
+                material: material_name(triangle_mesh.material_id()),
+            }
+        }
+        HittableObjectType::HittableObjectList(hittable_objects_list) => ObjectDescription::List {
+            objects: hittable_objects_list
+                .items()
+                .iter()
+                .map(|item| describe_object(item))
+                .collect(),
+        },
+        HittableObjectType::TranslateDecorator(translate_decorator) => {
+            ObjectDescription::Translate {
+                offset: vector(translate_decorator.offset()),
+                object: Box::new(describe_object(translate_decorator.inner())),
+            }
+        }
+        HittableObjectType::RotateYDecorator(rotate_y_decorator) => ObjectDescription::RotateY {
+            angle: rotate_y_decorator.angle(),
+            object: Box::new(describe_object(rotate_y_decorator.inner())),
+        },
+        HittableObjectType::ConstantDensityMedium(constant_density_medium) => {
+            ObjectDescription::ConstantDensityMedium {
+                density: constant_density_medium.density(),
+                material: material_name(constant_density_medium.phase_function_id()),
+                boundary: Box::new(describe_object(constant_density_medium.boundary())),
+            }
+        }
+    }
+}
+
+fn material_name(material_id: usize) -> String {
+    format!("material_{}", material_id)
+}
+
+fn point(p: &Point3<f64>) -> [f64; 3] {
+    [p.x, p.y, p.z]
+}
+
+fn vector(v: &Vector3<f64>) -> [f64; 3] {
+    [v.x, v.y, v.z]
+}
+
+fn color(c: &Rgb<f64>) -> [f64; 3] {
+    [c.r, c.g, c.b]
+}
@@ -1,29 +1,41 @@
-use nalgebra::{Point3, Vector3};
+use nalgebra::{Point3, Vector2, Vector3};
 use rand::Rng;
 use rgb::Rgb;
 
 use crate::{
-    core::{bvh::BvhTree, camera::Camera},
+    core::camera::Camera,
     material::{
         dielectric::Dielectric, diffuse_light::DiffuseLight, isotropic::Isotropic,
         lambertian::Lambertian, metal::Metal, MaterialType,
     },
     object::{
-        constant_density_medium::ConstantDensityMedium, hittable_objects_list::HittableObjectsList,
-        moving_sphere::MovingSphere, quad::Quad, rotate_y_decorator::RotateYDecorator,
-        sphere::Sphere, translate_decorator::TranslateDecorator, HittableObjectType,
+        constant_density_medium::ConstantDensityMedium,
+        hittable_objects_list::HittableObjectsList,
+        moving_sphere::MovingSphere,
+        quad::Quad,
+        rotate_y_decorator::RotateYDecorator,
+        sphere::Sphere,
+        translate_decorator::TranslateDecorator,
+        triangle::Triangle,
+        triangle_mesh::{MeshData, TriangleMesh},
+        HittableObjectType,
     },
     scene::SceneOptions,
     texture::{
         checker_texture::CheckerTexture, image_texture::ImageTexture, noise_texture::NoiseTexture,
         solid_color::SolidColor,
     },
-    utils::random_vector_generator,
+    utils::{random_vector_generator, rng},
 };
 
 use super::{Scene, SceneContent};
 
-pub fn scene_with_spheres(rows: usize, cols: usize, samples_per_pixel: Option<u32>) -> Scene {
+pub fn scene_with_spheres(
+    rows: usize,
+    cols: usize,
+    samples_per_pixel: Option<u32>,
+    max_leaf_size: usize,
+) -> Scene {
     // Materials
     let mut materials = generate_random_materials(rows, cols);
     let material_ground = Lambertian::from(Rgb::new(0.5, 0.5, 0.5)).into();
@@ -42,7 +54,7 @@ pub fn scene_with_spheres(rows: usize, cols: usize, samples_per_pixel: Option<u3
     world.push(Sphere::new(Point3::new(-4.0, 1.0, 0.0), 1.0, materials.len() - 2).into());
     world.push(Sphere::new(Point3::new(4.0, 1.0, 0.0), 1.0, materials.len() - 1).into());
 
-    let content = SceneContent::new(materials, world.into());
+    let content = SceneContent::new(materials, world, max_leaf_size);
     let camera = build_camera_for_spheres(samples_per_pixel);
     Scene::new(content, camera, Default::default())
 }
@@ -51,6 +63,7 @@ pub fn scene_with_moving_spheres(
     rows: usize,
     cols: usize,
     samples_per_pixel: Option<u32>,
+    max_leaf_size: usize,
 ) -> Scene {
     // Materials
     let mut materials = generate_random_materials(rows, cols);
@@ -73,12 +86,15 @@ pub fn scene_with_moving_spheres(
     world.push(Sphere::new(Point3::new(-4.0, 1.0, 0.0), 1.0, materials.len() - 2).into());
     world.push(Sphere::new(Point3::new(4.0, 1.0, 0.0), 1.0, materials.len() - 1).into());
 
-    let content = SceneContent::new(materials, world.into());
+    let content = SceneContent::new(materials, world, max_leaf_size);
     let camera = build_camera_for_spheres(samples_per_pixel);
     Scene::new(content, camera, Default::default())
 }
 
-pub fn scene_with_two_checker_spheres(samples_per_pixel: Option<u32>) -> Scene {
+pub fn scene_with_two_checker_spheres(
+    samples_per_pixel: Option<u32>,
+    max_leaf_size: usize,
+) -> Scene {
     // Materials
     let checker_even = SolidColor::new(Rgb::new(0.2, 0.3, 0.1));
     let checker_odd = SolidColor::new(Rgb::new(0.9, 0.9, 0.9));
@@ -92,7 +108,7 @@ pub fn scene_with_two_checker_spheres(samples_per_pixel: Option<u32>) -> Scene {
         HittableObjectType::Sphere(Sphere::new(Point3::new(0.0, 10.0, 0.0), 10.0, 0)),
     ];
 
-    let content = SceneContent::new(materials, world.into());
+    let content = SceneContent::new(materials, world, max_leaf_size);
 
     // Camera
     const WIDTH: u32 = 1200;
@@ -115,13 +131,13 @@ pub fn scene_with_two_checker_spheres(samples_per_pixel: Option<u32>) -> Scene {
     Scene::new(content, camera, Default::default())
 }
 
-pub fn scene_with_earthmap(samples_per_pixel: Option<u32>) -> Scene {
+pub fn scene_with_earthmap(samples_per_pixel: Option<u32>, max_leaf_size: usize) -> Scene {
     let earh_texture = ImageTexture::new("assets/earthmap.jpg").unwrap();
     let globe_material = Lambertian::new(earh_texture.into()).into();
     let materials = vec![globe_material];
     let globe = Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, 0).into();
 
-    let content = SceneContent::new(materials, BvhTree::from(vec![globe]));
+    let content = SceneContent::new(materials, vec![globe], max_leaf_size);
 
     const WIDTH: u32 = 1200;
     const ASPECT_RATIO: f64 = 16.0 / 9.0;
@@ -143,12 +159,13 @@ pub fn scene_with_earthmap(samples_per_pixel: Option<u32>) -> Scene {
     Scene::new(content, camera, Default::default())
 }
 
-pub fn scene_with_perlin_noise(samples_per_pixel: Option<u32>) -> Scene {
-    let materials = vec![Lambertian::new(NoiseTexture::new(4.0).into()).into()];
+pub fn scene_with_perlin_noise(samples_per_pixel: Option<u32>, max_leaf_size: usize) -> Scene {
+    let materials =
+        vec![Lambertian::new(NoiseTexture::new(4.0, rng::rng().random()).into()).into()];
     let bigger = Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, 0).into();
     let smaller = Sphere::new(Point3::new(0.0, 2.0, 0.0), 2.0, 0).into();
 
-    let content = SceneContent::new(materials, BvhTree::from(vec![bigger, smaller]));
+    let content = SceneContent::new(materials, vec![bigger, smaller], max_leaf_size);
 
     const WIDTH: u32 = 1200;
     const ASPECT_RATIO: f64 = 16.0 / 9.0;
@@ -170,7 +187,7 @@ pub fn scene_with_perlin_noise(samples_per_pixel: Option<u32>) -> Scene {
     Scene::new(content, camera, Default::default())
 }
 
-pub fn scene_with_quads(samples_per_pixel: Option<u32>) -> Scene {
+pub fn scene_with_quads(samples_per_pixel: Option<u32>, max_leaf_size: usize) -> Scene {
     let left_red: MaterialType = Lambertian::from(Rgb::new(1.0, 0.2, 0.2)).into();
     let back_green: MaterialType = Lambertian::from(Rgb::new(0.2, 1.0, 0.2)).into();
     let right_blue: MaterialType = Lambertian::from(Rgb::new(0.2, 0.2, 1.0)).into();
@@ -215,7 +232,7 @@ pub fn scene_with_quads(samples_per_pixel: Option<u32>) -> Scene {
     .into();
     let objects = vec![left, back, right, upper, lower];
 
-    let content = SceneContent::new(materials, objects.into());
+    let content = SceneContent::new(materials, objects, max_leaf_size);
 
     const WIDTH: u32 = 800;
     const ASPECT_RATIO: f64 = 1.0;
@@ -237,8 +254,8 @@ pub fn scene_with_quads(samples_per_pixel: Option<u32>) -> Scene {
     Scene::new(content, camera, Default::default())
 }
 
-pub fn scene_with_simple_light(samples_per_pixel: Option<u32>) -> Scene {
-    let perlin_texture = Lambertian::new(NoiseTexture::new(4.0).into()).into();
+pub fn scene_with_simple_light(samples_per_pixel: Option<u32>, max_leaf_size: usize) -> Scene {
+    let perlin_texture = Lambertian::new(NoiseTexture::new(4.0, rng::rng().random()).into()).into();
     // Brighter than (1,1,1) to light things around it
     let light = DiffuseLight::from(Rgb::new(4.0, 4.0, 4.0)).into();
     let materials = vec![perlin_texture, light];
@@ -255,7 +272,7 @@ pub fn scene_with_simple_light(samples_per_pixel: Option<u32>) -> Scene {
     let sphere_light = Sphere::new(Point3::new(0.0, 7.0, 0.0), 2.0, 1).into();
     let world = vec![ground, main_object, quad_light, sphere_light];
 
-    let content = SceneContent::new(materials, world.into());
+    let content = SceneContent::new(materials, world, max_leaf_size);
 
     const WIDTH: u32 = 1200;
     const ASPECT_RATIO: f64 = 16.0 / 9.0;
@@ -280,7 +297,7 @@ pub fn scene_with_simple_light(samples_per_pixel: Option<u32>) -> Scene {
     Scene::new(content, camera, options)
 }
 
-pub fn scene_with_cornell_box(samples_per_pixel: Option<u32>) -> Scene {
+pub fn scene_with_cornell_box(samples_per_pixel: Option<u32>, max_leaf_size: usize) -> Scene {
     let light = DiffuseLight::from(Rgb::new(15.0, 15.0, 15.0)).into();
     let red = Lambertian::from(Rgb::new(0.65, 0.05, 0.05)).into();
     let white = Lambertian::from(Rgb::new(0.73, 0.73, 0.73)).into();
@@ -366,7 +383,7 @@ pub fn scene_with_cornell_box(samples_per_pixel: Option<u32>) -> Scene {
         cuboid_bigger,
     ];
 
-    let content = SceneContent::new(materials, world.into());
+    let content = SceneContent::new(materials, world, max_leaf_size);
 
     const WIDTH: u32 = 800;
     const ASPECT_RATIO: f64 = 1.0;
@@ -392,7 +409,7 @@ pub fn scene_with_cornell_box(samples_per_pixel: Option<u32>) -> Scene {
     Scene::new(content, camera, options)
 }
 
-pub fn scene_with_fog_cornell_box(samples_per_pixel: Option<u32>) -> Scene {
+pub fn scene_with_fog_cornell_box(samples_per_pixel: Option<u32>, max_leaf_size: usize) -> Scene {
     let light = DiffuseLight::from(Rgb::new(15.0, 15.0, 15.0)).into();
     let red = Lambertian::from(Rgb::new(0.65, 0.05, 0.05)).into();
     let white = Lambertian::from(Rgb::new(0.73, 0.73, 0.73)).into();
@@ -482,7 +499,7 @@ pub fn scene_with_fog_cornell_box(samples_per_pixel: Option<u32>) -> Scene {
         cuboid_bigger,
     ];
 
-    let content = SceneContent::new(materials, world.into());
+    let content = SceneContent::new(materials, world, max_leaf_size);
 
     const WIDTH: u32 = 800;
     const ASPECT_RATIO: f64 = 1.0;
@@ -508,7 +525,7 @@ pub fn scene_with_fog_cornell_box(samples_per_pixel: Option<u32>) -> Scene {
     Scene::new(content, camera, options)
 }
 
-pub fn scene_with_all_effects(samples_per_pixel: Option<u32>) -> Scene {
+pub fn scene_with_all_effects(samples_per_pixel: Option<u32>, max_leaf_size: usize) -> Scene {
     let mut materials: Vec<MaterialType> = vec![];
     let mut objects: Vec<HittableObjectType> = vec![];
 
@@ -518,7 +535,7 @@ pub fn scene_with_all_effects(samples_per_pixel: Option<u32>) -> Scene {
 
     const BOXES_PER_SIDE: usize = 20;
 
-    let mut rng = rand::rng();
+    let mut rng = rng::rng();
     (0..BOXES_PER_SIDE).for_each(|i| {
         (0..BOXES_PER_SIDE).for_each(|j| {
             let i = i as f64;
@@ -583,7 +600,7 @@ pub fn scene_with_all_effects(samples_per_pixel: Option<u32>) -> Scene {
     objects.push(earth_sphere);
 
     // Perlin noise sphere
-    let perlin_noise_texture = Lambertian::new(NoiseTexture::new(0.2).into()).into();
+    let perlin_noise_texture = Lambertian::new(NoiseTexture::new(0.2, rng.random()).into()).into();
     materials.push(perlin_noise_texture);
     let perlin_noise_sphere =
         Sphere::new(Point3::new(220.0, 280.0, 300.0), 80.0, materials.len() - 1).into();
@@ -604,7 +621,7 @@ pub fn scene_with_all_effects(samples_per_pixel: Option<u32>) -> Scene {
     let stacked = TranslateDecorator::new(stacked, Vector3::new(-100.0, 270.0, 395.0)).into();
     objects.push(stacked);
 
-    let content = SceneContent::new(materials, objects.into());
+    let content = SceneContent::new(materials, objects, max_leaf_size);
 
     const WIDTH: u32 = 800;
     const ASPECT_RATIO: f64 = 1.0;
@@ -630,11 +647,73 @@ pub fn scene_with_all_effects(samples_per_pixel: Option<u32>) -> Scene {
     Scene::new(content, camera, options)
 }
 
+pub fn scene_with_triangles(samples_per_pixel: Option<u32>, max_leaf_size: usize) -> Scene {
+    let ground = Lambertian::from(Rgb::new(0.5, 0.5, 0.5)).into();
+    let earth_texture = ImageTexture::new("assets/earthmap.jpg").unwrap();
+    let globe = Lambertian::new(earth_texture.into()).into();
+    let red = Lambertian::from(Rgb::new(0.65, 0.05, 0.05)).into();
+    let metal = Metal::new(Rgb::new(0.8, 0.8, 0.9), 0.05).into();
+    let materials = vec![ground, globe, red, metal];
+
+    let ground = Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000.0, 0).into();
+
+    // Smooth shaded sphere made of triangles
+    let globe_mesh = generate_uv_sphere_mesh(Point3::new(0.0, 1.0, 0.0), 1.0, 32, 64);
+    let globe = TriangleMesh::new(globe_mesh, 1).into();
+
+    // Flat shaded tetrahedron
+    let apex = Point3::new(-2.5, 1.6, -0.5);
+    let base = [
+        Point3::new(-3.3, 0.0, 0.2),
+        Point3::new(-1.7, 0.0, 0.2),
+        Point3::new(-2.5, 0.0, -1.3),
+    ];
+    let tetrahedron_mesh = MeshData::new(
+        vec![base[0], base[1], base[2], apex],
+        None,
+        None,
+        vec![[0, 1, 3], [1, 2, 3], [2, 0, 3], [0, 2, 1]],
+    );
+    let tetrahedron = TriangleMesh::new(tetrahedron_mesh, 2).into();
+
+    // Single mirror-like triangle
+    let mirror = Triangle::new(
+        Point3::new(1.5, 0.0, -1.0),
+        Point3::new(3.5, 0.0, -0.5),
+        Point3::new(2.5, 2.0, -1.0),
+        3,
+    )
+    .into();
+
+    let world = vec![ground, globe, tetrahedron, mirror];
+
+    let content = SceneContent::new(materials, world, max_leaf_size);
+
+    const WIDTH: u32 = 1200;
+    const ASPECT_RATIO: f64 = 16.0 / 9.0;
+    const DEFAULT_SAMPLES_PER_PIXEL: u32 = 100;
+    const MAX_BOUNCE_DEPTH: u32 = 50;
+    const V_FOV: f64 = 30.0;
+    const CENTER: Point3<f64> = Point3::new(0.0, 2.5, 9.0);
+    const LOOK_AT: Point3<f64> = Point3::new(0.0, 0.8, 0.0);
+    let camera = Camera::builder()
+        .width(WIDTH)
+        .aspect_ratio(ASPECT_RATIO)
+        .samples_per_pixel(samples_per_pixel.unwrap_or(DEFAULT_SAMPLES_PER_PIXEL))
+        .max_bounce_depth(MAX_BOUNCE_DEPTH)
+        .vertical_fov_angles(V_FOV)
+        .center(CENTER)
+        .look_at(LOOK_AT)
+        .build();
+
+    Scene::new(content, camera, Default::default())
+}
+
 fn generate_random_materials(rows: usize, cols: usize) -> Vec<MaterialType> {
     let count = rows * cols;
     (0..count)
         .map(|_| {
-            let mut rng = rand::rng();
+            let mut rng = rng::rng();
             let choose_material: f64 = rng.random();
             if choose_material < 0.8 {
                 let color_vec = random_vector_generator::random_vector3(0.0..1.0);
@@ -664,7 +743,7 @@ fn generate_random_spheres(rows: usize, cols: usize) -> Vec<HittableObjectType>
     let half_cols = (cols as f64 / 2.0) as i32;
     for i in -half_rows..half_rows {
         for j in -half_cols..half_cols {
-            let mut rng = rand::rng();
+            let mut rng = rng::rng();
             let center = Point3::new(
                 i as f64 + 0.9 * rng.random::<f64>(),
                 0.2,
@@ -684,7 +763,7 @@ fn generate_random_moving_spheres(rows: usize, cols: usize) -> Vec<HittableObjec
     let half_cols = (cols as f64 / 2.0) as i32;
     for i in -half_rows..half_rows {
         for j in -half_cols..half_cols {
-            let mut rng = rand::rng();
+            let mut rng = rng::rng();
             let from = Point3::new(
                 i as f64 + 0.9 * rng.random::<f64>(),
                 0.2,
@@ -699,6 +778,53 @@ fn generate_random_moving_spheres(rows: usize, cols: usize) -> Vec<HittableObjec
     output
 }
 
+/// Returns sphere mesh with per-vertex normals and texture coordinates matching `Sphere`'s ones.
+fn generate_uv_sphere_mesh(
+    center: Point3<f64>,
+    radius: f64,
+    stacks: usize,
+    slices: usize,
+) -> MeshData {
+    let mut vertices = Vec::with_capacity((stacks + 1) * (slices + 1));
+    let mut normals = Vec::with_capacity(vertices.capacity());
+    let mut uvs = Vec::with_capacity(vertices.capacity());
+    for i in 0..=stacks {
+        let theta = std::f64::consts::PI * i as f64 / stacks as f64;
+        for j in 0..=slices {
+            let phi = 2.0 * std::f64::consts::PI * j as f64 / slices as f64;
+            let normal = Vector3::new(
+                -phi.cos() * theta.sin(),
+                -theta.cos(),
+                phi.sin() * theta.sin(),
+            );
+            vertices.push(center + radius * normal);
+            normals.push(normal);
+            uvs.push(Vector2::new(
+                j as f64 / slices as f64,
+                i as f64 / stacks as f64,
+            ));
+        }
+    }
+
+    // All vertices of the first and the last stack are at the poles, so faces with two
+    // of them have zero area and are skipped
+    let mut faces = Vec::with_capacity(2 * stacks.saturating_sub(1) * slices);
+    for i in 0..stacks {
+        for j in 0..slices {
+            let current = i * (slices + 1) + j;
+            let above = current + slices + 1;
+            if i > 0 {
+                faces.push([current, above, current + 1]);
+            }
+            if i + 1 < stacks {
+                faces.push([current + 1, above, above + 1]);
+            }
+        }
+    }
+
+    MeshData::new(vertices, Some(normals), Some(uvs), faces)
+}
+
 fn build_camera_for_spheres(samples_per_pixel: Option<u32>) -> Camera {
     const WIDTH: u32 = 1200;
     const ASPECT_RATIO: f64 = 16.0 / 9.0;
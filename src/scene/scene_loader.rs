@@ -0,0 +1,559 @@
+use std::{
+    collections::HashMap,
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{bail, Context, Error, Result};
+use nalgebra::{Point3, Vector2, Vector3};
+use rand::Rng;
+use rgb::Rgb;
+
+use crate::{
+    core::camera::Camera,
+    material::{
+        dielectric::Dielectric, diffuse_light::DiffuseLight, isotropic::Isotropic,
+        lambertian::Lambertian, metal::Metal, MaterialType,
+    },
+    object::{
+        constant_density_medium::ConstantDensityMedium,
+        hittable_objects_list::HittableObjectsList,
+        moving_sphere::MovingSphere,
+        quad::Quad,
+        rotate_y_decorator::RotateYDecorator,
+        sphere::Sphere,
+        translate_decorator::TranslateDecorator,
+        triangle::Triangle,
+        triangle_mesh::{MeshData, TriangleMesh},
+        HittableObjectType,
+    },
+    texture::{
+        checker_texture::CheckerTexture, image_texture::ImageTexture, noise_texture::NoiseTexture,
+        solid_color::SolidColor, NonRecursiveTexture, TextureType,
+    },
+    utils::rng,
+};
+
+use super::{
+    obj_loader,
+    scene_description::{
+        CameraDescription, MaterialDescription, ObjectDescription, SceneDescription,
+        TextureDescription, TextureReference,
+    },
+    Scene, SceneContent, SceneOptions,
+};
+
+/// Loads scene from the file at `path`.
+///
+/// If `samples_per_pixel` is provided, it overrides value from the scene file.
+/// BVH of the scene is built with leaves of at most `max_leaf_size` objects.
+pub fn load_scene<P: AsRef<Path>>(
+    path: P,
+    samples_per_pixel: Option<u32>,
+    max_leaf_size: usize,
+) -> Result<Scene> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("cannot read scene file '{}'", path.display()))?;
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    parse_scene(&source, base_dir, samples_per_pixel, max_leaf_size)
+        .with_context(|| format!("invalid scene file '{}'", path.display()))
+}
+
+/// Parses scene from `source`. Relative paths in the scene (e.g. image textures)
+/// are resolved against `base_dir`.
+pub fn parse_scene(
+    source: &str,
+    base_dir: &Path,
+    samples_per_pixel: Option<u32>,
+    max_leaf_size: usize,
+) -> Result<Scene> {
+    let description: SceneDescription = toml::from_str(source)?;
+    let mut loader = SceneLoader {
+        source,
+        base_dir,
+        textures: HashMap::new(),
+        materials: Vec::with_capacity(description.materials.len()),
+        material_ids: HashMap::new(),
+    };
+
+    // Noise textures without their own seed are generated from the same seed as the rendered image
+    rng::reseed(description.camera.get_ref().seed.into());
+
+    for texture in &description.textures {
+        let span = texture.span();
+        let texture = texture.get_ref();
+        if loader.textures.contains_key(&texture.name) {
+            return Err(loader.error_at(span, format!("duplicated texture '{}'", texture.name)));
+        }
+        let built = loader.build_texture(&texture.texture, &span)?;
+        loader.textures.insert(texture.name.clone(), built);
+    }
+
+    for material in &description.materials {
+        let span = material.span();
+        let material = material.get_ref();
+        if loader.material_ids.contains_key(&material.name) {
+            return Err(loader.error_at(span, format!("duplicated material '{}'", material.name)));
+        }
+        let built = loader.build_material(&material.material, &span)?;
+        loader.materials.push(built);
+        loader
+            .material_ids
+            .insert(material.name.clone(), loader.materials.len() - 1);
+    }
+
+    if description.objects.is_empty() {
+        bail!("scene must contain at least one object");
+    }
+    let mut objects = Vec::with_capacity(description.objects.len());
+    for object in &description.objects {
+        let built = loader.build_object(object.get_ref(), &object.span())?;
+        objects.push(built);
+    }
+
+    let camera = loader.build_camera(
+        description.camera.get_ref(),
+        &description.camera.span(),
+        samples_per_pixel,
+    )?;
+    let options = SceneOptions::builder()
+        .background(color(description.options.background))
+        .build();
+
+    let content = SceneContent::new(loader.materials, objects, max_leaf_size);
+    Ok(Scene::new(content, camera, options))
+}
+
+struct SceneLoader<'a> {
+    source: &'a str,
+    base_dir: &'a Path,
+    textures: HashMap<String, TextureType>,
+    materials: Vec<MaterialType>,
+    material_ids: HashMap<String, usize>,
+}
+
+impl SceneLoader<'_> {
+    fn build_camera(
+        &self,
+        camera: &CameraDescription,
+        span: &Range<usize>,
+        samples_per_pixel: Option<u32>,
+    ) -> Result<Camera> {
+        if camera.width == 0 {
+            return Err(self.error_at(span.clone(), "camera width must be positive"));
+        }
+        if camera.aspect_ratio <= 0.0 {
+            return Err(self.error_at(span.clone(), "camera aspect ratio must be positive"));
+        }
+        if camera.vertical_fov_angles <= 0.0 || camera.vertical_fov_angles >= 180.0 {
+            return Err(self.error_at(
+                span.clone(),
+                "camera vertical fov must be in range (0, 180) degrees",
+            ));
+        }
+        if camera.center == camera.look_at {
+            return Err(self.error_at(span.clone(), "camera center and look at must differ"));
+        }
+        if camera.samples_per_pixel == 0 {
+            return Err(self.error_at(span.clone(), "camera samples per pixel must be positive"));
+        }
+        if camera.defocus_angle < 0.0 {
+            return Err(self.error_at(span.clone(), "camera defocus angle cannot be negative"));
+        }
+        if camera.focus_distance <= 0.0 {
+            return Err(self.error_at(span.clone(), "camera focus distance must be positive"));
+        }
+
+        Ok(Camera::builder()
+            .width(camera.width)
+            .aspect_ratio(camera.aspect_ratio)
+            .samples_per_pixel(samples_per_pixel.unwrap_or(camera.samples_per_pixel))
+            .max_bounce_depth(camera.max_bounce_depth)
+            .vertical_fov_angles(camera.vertical_fov_angles)
+            .center(point(camera.center))
+            .look_at(point(camera.look_at))
+            .relative_up(vector(camera.relative_up))
+            .defocus_angle(camera.defocus_angle)
+            .focus_distance(camera.focus_distance)
+            .seed(camera.seed)
+            .build())
+    }
+
+    fn build_texture(
+        &self,
+        texture: &TextureDescription,
+        span: &Range<usize>,
+    ) -> Result<TextureType> {
+        let texture = match texture {
+            TextureDescription::SolidColor { color: c } => SolidColor::new(color(*c)).into(),
+            TextureDescription::Image { path } => {
+                let full_path = self.resolve_path(path);
+                ImageTexture::new(&full_path)
+                    .with_context(|| {
+                        self.message_at(
+                            span.clone(),
+                            format!("cannot load image texture '{}'", full_path.display()),
+                        )
+                    })?
+                    .into()
+            }
+            TextureDescription::Noise {
+                scale,
+                turbulance_depth,
+                turbulance_factor,
+                seed,
+            } => NoiseTexture::with_turbulance_params(
+                *scale,
+                turbulance_depth.unwrap_or(NoiseTexture::DEFAULT_TURBULANCE_DEPTH),
+                turbulance_factor.unwrap_or(NoiseTexture::DEFAULT_TURBULANCE_FACTOR),
+                seed.unwrap_or_else(|| rng::rng().random()),
+            )
+            .into(),
+            TextureDescription::Checker { scale, even, odd } => {
+                if *scale <= 0.0 {
+                    return Err(self.error_at(span.clone(), "checker scale must be positive"));
+                }
+                let even = self.non_recursive_texture(even, span)?;
+                let odd = self.non_recursive_texture(odd, span)?;
+                CheckerTexture::new(*scale, even, odd).into()
+            }
+        };
+        Ok(texture)
+    }
+
+    fn non_recursive_texture(
+        &self,
+        name: &str,
+        span: &Range<usize>,
+    ) -> Result<NonRecursiveTexture> {
+        match self.textures.get(name) {
+            Some(TextureType::NonRecursive(texture)) => Ok(texture.clone()),
+            Some(_) => Err(self.error_at(
+                span.clone(),
+                format!("texture '{}' cannot be used inside checker texture", name),
+            )),
+            None => Err(self.error_at(span.clone(), format!("unknown texture '{}'", name))),
+        }
+    }
+
+    fn texture(&self, reference: &TextureReference, span: &Range<usize>) -> Result<TextureType> {
+        match reference {
+            TextureReference::Color(c) => Ok(SolidColor::new(color(*c)).into()),
+            TextureReference::Named(name) => {
+                self.textures.get(name).cloned().ok_or_else(|| {
+                    self.error_at(span.clone(), format!("unknown texture '{}'", name))
+                })
+            }
+        }
+    }
+
+    fn build_material(
+        &self,
+        material: &MaterialDescription,
+        span: &Range<usize>,
+    ) -> Result<MaterialType> {
+        let material = match material {
+            MaterialDescription::Lambertian { texture } => {
+                Lambertian::new(self.texture(texture, span)?).into()
+            }
+            MaterialDescription::Metal { albedo, fuzziness } => {
+                if *fuzziness < 0.0 {
+                    return Err(self.error_at(span.clone(), "metal fuzziness cannot be negative"));
+                }
+                Metal::new(color(*albedo), *fuzziness).into()
+            }
+            MaterialDescription::Dielectric { refraction_index } => {
+                if *refraction_index <= 0.0 {
+                    return Err(self.error_at(span.clone(), "refraction index must be positive"));
+                }
+                Dielectric::new(*refraction_index).into()
+            }
+            MaterialDescription::DiffuseLight { texture } => {
+                DiffuseLight::new(self.texture(texture, span)?).into()
+            }
+            MaterialDescription::Isotropic { texture } => {
+                Isotropic::new(self.texture(texture, span)?).into()
+            }
+        };
+        Ok(material)
+    }
+
+    fn build_object(
+        &mut self,
+        object: &ObjectDescription,
+        span: &Range<usize>,
+    ) -> Result<HittableObjectType> {
+        let object = match object {
+            ObjectDescription::Sphere {
+                center,
+                radius,
+                material,
+            } => {
+                self.check_radius(*radius, span)?;
+                Sphere::new(point(*center), *radius, self.material_id(material, span)?).into()
+            }
+            ObjectDescription::MovingSphere {
+                from,
+                to,
+                radius,
+                material,
+            } => {
+                self.check_radius(*radius, span)?;
+                MovingSphere::new(
+                    point(*from),
+                    point(*to),
+                    *radius,
+                    self.material_id(material, span)?,
+                )
+                .into()
+            }
+            ObjectDescription::Quad {
+                start,
+                u,
+                v,
+                material,
+            } => {
+                let (u, v) = (vector(*u), vector(*v));
+                if u.cross(&v).norm_squared() == 0.0 {
+                    return Err(self.error_at(span.clone(), "quad sides cannot be parallel"));
+                }
+                Quad::new(point(*start), u, v, self.material_id(material, span)?).into()
+            }
+            ObjectDescription::Triangle {
+                vertices,
+                normals,
+                uvs,
+                material,
+            } => {
+                let mesh = self.build_mesh_data(
+                    vertices.to_vec(),
+                    normals.map(|normals| normals.to_vec()),
+                    uvs.map(|uvs| uvs.to_vec()),
+                    vec![[0, 1, 2]],
+                    span,
+                )?;
+                Triangle::from_mesh(Arc::new(mesh), 0, self.material_id(material, span)?).into()
+            }
+            ObjectDescription::TriangleMesh {
+                vertices,
+                normals,
+                uvs,
+                faces,
+                material,
+            } => {
+                if faces.is_empty() {
+                    return Err(self.error_at(span.clone(), "triangle mesh cannot be empty"));
+                }
+                let mesh = self.build_mesh_data(
+                    vertices.clone(),
+                    normals.clone(),
+                    uvs.clone(),
+                    faces.clone(),
+                    span,
+                )?;
+                TriangleMesh::new(mesh, self.material_id(material, span)?).into()
+            }
+            ObjectDescription::Obj { path, material } => {
+                let full_path = self.resolve_path(path);
+                let model = match material {
+                    Some(material) => obj_loader::load_obj_with_material(
+                        &full_path,
+                        self.material_id(material, span)?,
+                    ),
+                    None => obj_loader::load_obj(&full_path, self.materials.len()),
+                }
+                .with_context(|| self.message_at(span.clone(), "cannot load obj file"))?;
+                self.materials.extend(model.materials);
+                match <[HittableObjectType; 1]>::try_from(model.objects) {
+                    Ok([object]) => object,
+                    Err(objects) => HittableObjectsList::from(objects).into(),
+                }
+            }
+            ObjectDescription::Cuboid {
+                start,
+                end,
+                material,
+            } => {
+                let (start, end) = (point(*start), point(*end));
+                if (0..3).any(|i| start[i] == end[i]) {
+                    return Err(self.error_at(span.clone(), "cuboid cannot be flat"));
+                }
+                Quad::cuboid(start, end, self.material_id(material, span)?).into()
+            }
+            ObjectDescription::List { objects } => {
+                if objects.is_empty() {
+                    return Err(self.error_at(span.clone(), "list cannot be empty"));
+                }
+                let items = objects
+                    .iter()
+                    .map(|object| self.build_object(object, span))
+                    .collect::<Result<Vec<_>>>()?;
+                HittableObjectsList::from(items).into()
+            }
+            ObjectDescription::Translate { offset, object } => {
+                TranslateDecorator::new(self.build_object(object, span)?, vector(*offset)).into()
+            }
+            ObjectDescription::RotateY { angle, object } => {
+                RotateYDecorator::new(self.build_object(object, span)?, *angle).into()
+            }
+            ObjectDescription::ConstantDensityMedium {
+                density,
+                material,
+                boundary,
+            } => {
+                if density.abs() <= 0.0001 {
+                    return Err(self.error_at(span.clone(), "medium density is too small"));
+                }
+                let boundary = self.build_object(boundary, span)?;
+                ConstantDensityMedium::new(
+                    Box::new(boundary),
+                    *density,
+                    self.material_id(material, span)?,
+                )
+                .into()
+            }
+        };
+        Ok(object)
+    }
+
+    fn build_mesh_data(
+        &self,
+        vertices: Vec<[f64; 3]>,
+        normals: Option<Vec<[f64; 3]>>,
+        uvs: Option<Vec<[f64; 2]>>,
+        faces: Vec<[usize; 3]>,
+        span: &Range<usize>,
+    ) -> Result<MeshData> {
+        if let Some(id) = faces.iter().flatten().find(|&&id| id >= vertices.len()) {
+            return Err(self.error_at(span.clone(), format!("vertex index {} is out of range", id)));
+        }
+        if normals.as_ref().is_some_and(|n| n.len() != vertices.len()) {
+            return Err(self.error_at(
+                span.clone(),
+                "number of normals must match number of vertices",
+            ));
+        }
+        if normals
+            .iter()
+            .flatten()
+            .any(|n| n.iter().all(|c| *c == 0.0))
+        {
+            return Err(self.error_at(span.clone(), "normals cannot be zero vectors"));
+        }
+        if uvs.as_ref().is_some_and(|uv| uv.len() != vertices.len()) {
+            return Err(self.error_at(span.clone(), "number of uvs must match number of vertices"));
+        }
+        Ok(MeshData::new(
+            vertices.into_iter().map(point).collect(),
+            normals.map(|normals| normals.into_iter().map(vector).collect()),
+            uvs.map(|uvs| uvs.into_iter().map(Vector2::from).collect()),
+            faces,
+        ))
+    }
+
+    fn check_radius(&self, radius: f64, span: &Range<usize>) -> Result<()> {
+        if radius <= 0.0 {
+            return Err(self.error_at(span.clone(), "sphere radius must be positive"));
+        }
+        Ok(())
+    }
+
+    fn material_id(&self, name: &str, span: &Range<usize>) -> Result<usize> {
+        self.material_ids
+            .get(name)
+            .copied()
+            .ok_or_else(|| self.error_at(span.clone(), format!("unknown material '{}'", name)))
+    }
+
+    fn resolve_path(&self, path: &str) -> PathBuf {
+        let path = Path::new(path);
+        if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            self.base_dir.join(path)
+        }
+    }
+
+    fn error_at<M: AsRef<str>>(&self, span: Range<usize>, message: M) -> Error {
+        Error::msg(self.message_at(span, message))
+    }
+
+    /// Prefixes `message` with line number (counted from 1) on which `span` starts.
+    fn message_at<M: AsRef<str>>(&self, span: Range<usize>, message: M) -> String {
+        let start = span.start.min(self.source.len());
+        let line = self.source[..start].matches('\n').count() + 1;
+        format!("line {}: {}", line, message.as_ref())
+    }
+}
+
+fn point(coords: [f64; 3]) -> Point3<f64> {
+    Point3::from(coords)
+}
+
+fn vector(coords: [f64; 3]) -> Vector3<f64> {
+    Vector3::from(coords)
+}
+
+fn color(channels: [f64; 3]) -> Rgb<f64> {
+    Rgb::from(channels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns error of loading a scene with a single sphere and `camera` parameters,
+    /// which is reported at the `[camera]` header on line 2.
+    fn camera_error(camera: &str) -> String {
+        let source = format!(
+            "# Test scene
+[camera]
+{camera}
+
+[[materials]]
+name = \"white\"
+type = \"lambertian\"
+texture = [0.5, 0.5, 0.5]
+
+[[objects]]
+type = \"sphere\"
+center = [0.0, 0.0, -2.0]
+radius = 1.0
+material = \"white\"
+"
+        );
+        let error = parse_scene(&source, Path::new("."), None, 4)
+            .err()
+            .expect("loading should fail");
+        format!("{error:#}")
+    }
+
+    #[test]
+    fn zero_samples_per_pixel_is_rejected() {
+        assert_eq!(
+            camera_error("samples_per_pixel = 0"),
+            "line 2: camera samples per pixel must be positive"
+        );
+    }
+
+    #[test]
+    fn negative_defocus_angle_is_rejected() {
+        assert_eq!(
+            camera_error("defocus_angle = -0.5"),
+            "line 2: camera defocus angle cannot be negative"
+        );
+    }
+
+    #[test]
+    fn non_positive_focus_distance_is_rejected() {
+        for focus_distance in ["0.0", "-2.0"] {
+            assert_eq!(
+                camera_error(&format!("focus_distance = {focus_distance}")),
+                "line 2: camera focus distance must be positive"
+            );
+        }
+    }
+}
@@ -22,6 +22,18 @@ impl CheckerTexture {
             odd_texture,
         }
     }
+
+    pub fn scale(&self) -> f64 {
+        1.0 / self.inversed_scale
+    }
+
+    pub fn even_texture(&self) -> &NonRecursiveTexture {
+        &self.even_texture
+    }
+
+    pub fn odd_texture(&self) -> &NonRecursiveTexture {
+        &self.odd_texture
+    }
 }
 
 impl Texture for CheckerTexture {
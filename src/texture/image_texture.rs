@@ -1,4 +1,4 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use image::{ImageReader, RgbImage};
@@ -10,12 +10,21 @@ use super::Texture;
 #[derive(Clone)]
 pub struct ImageTexture {
     img: RgbImage,
+    /// Path from which the image was loaded
+    path: PathBuf,
 }
 
 impl ImageTexture {
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let img = ImageReader::open(path)?.decode()?.to_rgb8();
-        Ok(Self { img })
+        let img = ImageReader::open(&path)?.decode()?.to_rgb8();
+        Ok(Self {
+            img,
+            path: path.as_ref().to_path_buf(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
     }
 }
 
@@ -5,21 +5,25 @@ use crate::effects::perlin_noise::PerlinNoise;
 
 use super::Texture;
 
+/// Turbulent marble-like texture, whose noise is generated from `seed`.
+/// Seed is `u32`, so it always fits into integers of the scene files.
 #[derive(Clone)]
 pub struct NoiseTexture {
     perlin_noise: PerlinNoise,
+    seed: u32,
     scale: f64,
     turbulance_depth: usize,
     turbulance_factor: f64,
 }
 
 impl NoiseTexture {
-    const DEFAULT_TURBULANCE_DEPTH: usize = 7;
-    const DEFAULT_TURBULANCE_FACTOR: f64 = 10.0;
+    pub const DEFAULT_TURBULANCE_DEPTH: usize = 7;
+    pub const DEFAULT_TURBULANCE_FACTOR: f64 = 10.0;
 
-    pub fn new(scale: f64) -> Self {
+    pub fn new(scale: f64, seed: u32) -> Self {
         Self {
-            perlin_noise: PerlinNoise::default(),
+            perlin_noise: PerlinNoise::new(seed.into()),
+            seed,
             scale,
             turbulance_depth: Self::DEFAULT_TURBULANCE_DEPTH,
             turbulance_factor: Self::DEFAULT_TURBULANCE_FACTOR,
@@ -30,14 +34,32 @@ impl NoiseTexture {
         scale: f64,
         turbulance_depth: usize,
         turbulance_factor: f64,
+        seed: u32,
     ) -> Self {
         Self {
-            perlin_noise: PerlinNoise::default(),
+            perlin_noise: PerlinNoise::new(seed.into()),
+            seed,
             scale,
             turbulance_depth,
             turbulance_factor,
         }
     }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    pub fn turbulance_depth(&self) -> usize {
+        self.turbulance_depth
+    }
+
+    pub fn turbulance_factor(&self) -> f64 {
+        self.turbulance_factor
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
 }
 
 impl Texture for NoiseTexture {
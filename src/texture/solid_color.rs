@@ -12,6 +12,10 @@ impl SolidColor {
     pub fn new(albedo: Rgb<f64>) -> Self {
         Self { albedo }
     }
+
+    pub fn albedo(&self) -> &Rgb<f64> {
+        &self.albedo
+    }
 }
 
 impl Texture for SolidColor {
@@ -0,0 +1,28 @@
+use rgb::Rgb;
+
+/// Maps `t` from `[0, 1]` range (values outside are clamped) to color going from blue
+/// through green to red.
+pub fn heatmap(t: f64) -> Rgb<f64> {
+    let t = t.clamp(0.0, 1.0);
+    if t < 0.5 {
+        Rgb::new(0.0, 2.0 * t, 1.0 - 2.0 * t)
+    } else {
+        Rgb::new(2.0 * t - 1.0, 2.0 - 2.0 * t, 0.0)
+    }
+}
+
+/// Returns distinct, fully saturated color for every `id`, neighbouring ids get very different hues.
+pub fn id_color(id: usize) -> Rgb<f64> {
+    // Multiples of the golden ratio are spread evenly over the hue circle
+    const GOLDEN_RATIO_CONJUGATE: f64 = 0.618_033_988_749_895;
+    let hue = (id as f64 * GOLDEN_RATIO_CONJUGATE).fract() * 6.0;
+    let x = 1.0 - (hue % 2.0 - 1.0).abs();
+    match hue as u32 {
+        0 => Rgb::new(1.0, x, 0.0),
+        1 => Rgb::new(x, 1.0, 0.0),
+        2 => Rgb::new(0.0, 1.0, x),
+        3 => Rgb::new(0.0, x, 1.0),
+        4 => Rgb::new(x, 0.0, 1.0),
+        _ => Rgb::new(1.0, 0.0, x),
+    }
+}
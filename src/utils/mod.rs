@@ -1 +1,4 @@
+pub mod color_map;
+pub mod orthonormal_basis;
 pub mod random_vector_generator;
+pub mod rng;
@@ -0,0 +1,34 @@
+use nalgebra::{Unit, UnitVector3, Vector3};
+
+/// Orthonormal basis built around `w` axis, used to transform directions sampled
+/// in local coordinates (where `w` is the Z axis) into world coordinates.
+pub struct OrthonormalBasis {
+    u: UnitVector3<f64>,
+    v: UnitVector3<f64>,
+    w: UnitVector3<f64>,
+}
+
+impl OrthonormalBasis {
+    pub fn new(w: UnitVector3<f64>) -> Self {
+        // Any vector not parallel to `w` works as a helper
+        let helper = if w.x.abs() > 0.9 {
+            Vector3::new(0.0, 1.0, 0.0)
+        } else {
+            Vector3::new(1.0, 0.0, 0.0)
+        };
+        let v = Unit::new_normalize(w.cross(&helper));
+        let u = Unit::new_unchecked(w.cross(&v));
+        OrthonormalBasis { u, v, w }
+    }
+
+    pub fn w(&self) -> &UnitVector3<f64> {
+        &self.w
+    }
+
+    /// Transforms `local` vector from basis coordinates into world coordinates.
+    pub fn local_to_world(&self, local: &Vector3<f64>) -> Vector3<f64> {
+        local.x * self.u.into_inner()
+            + local.y * self.v.into_inner()
+            + local.z * self.w.into_inner()
+    }
+}
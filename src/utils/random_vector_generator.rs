@@ -1,10 +1,12 @@
-use std::ops::Range;
+use std::{f64::consts::PI, ops::Range};
 
-use nalgebra::{Unit, UnitVector2, UnitVector3, Vector2, Vector3};
+use nalgebra::{Unit, UnitVector3, Vector2, Vector3};
 use rand::Rng;
 
+use super::rng;
+
 pub fn random_vector3(range: Range<f64>) -> Vector3<f64> {
-    let mut rng = rand::rng();
+    let mut rng = rng::rng();
     Vector3::new(
         rng.random_range(range.clone()),
         rng.random_range(range.clone()),
@@ -25,14 +27,39 @@ pub fn random_unit_vector3_in_sphere() -> UnitVector3<f64> {
     }
 }
 
-/// Works the same as `random_unit_vector3_in_sphere` but it's in 2D..
-pub fn random_unit_vector2_in_disk() -> UnitVector2<f64> {
-    loop {
-        let p3 = random_vector3(-1.0..1.0);
-        let p = Vector2::new(p3.x, p3.y);
-        let len_squared = p.norm_squared();
-        if (1e-160..=1.0).contains(&len_squared) {
-            return Unit::new_unchecked(p);
-        }
+/// Maps sample `u` from `[0, 1)^2` to unit vector on the hemisphere around Z axis, with probability
+/// density proportional to the cosine of its angle to Z axis (`cos / PI`).
+pub fn cosine_direction(u: &Vector2<f64>) -> UnitVector3<f64> {
+    let phi = 2.0 * PI * u.x;
+    let radius = u.y.sqrt();
+    Unit::new_normalize(Vector3::new(
+        phi.cos() * radius,
+        phi.sin() * radius,
+        (1.0 - u.y).sqrt(),
+    ))
+}
+
+/// Maps sample `u` from `[0, 1)^2` to unit vector distributed uniformly over the whole sphere.
+pub fn sphere_direction(u: &Vector2<f64>) -> UnitVector3<f64> {
+    let z = 1.0 - 2.0 * u.x;
+    let radius = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * u.y;
+    Unit::new_normalize(Vector3::new(radius * phi.cos(), radius * phi.sin(), z))
+}
+
+/// Maps sample `u` from `[0, 1)^2` to point distributed uniformly inside the unit disk.
+///
+/// Uses Shirley's concentric mapping, which keeps stratification of the samples
+/// better than polar coordinates.
+pub fn point_in_disk(u: &Vector2<f64>) -> Vector2<f64> {
+    let offset = 2.0 * u - Vector2::new(1.0, 1.0);
+    if offset.x == 0.0 && offset.y == 0.0 {
+        return offset;
     }
+    let (radius, theta) = if offset.x.abs() > offset.y.abs() {
+        (offset.x, PI / 4.0 * (offset.y / offset.x))
+    } else {
+        (offset.y, PI / 2.0 - PI / 4.0 * (offset.x / offset.y))
+    };
+    radius * Vector2::new(theta.cos(), theta.sin())
 }
@@ -0,0 +1,86 @@
+use std::cell::RefCell;
+
+use rand::{rngs::SmallRng, RngCore, SeedableRng};
+
+/// Seed used by threads which were never reseeded.
+pub const DEFAULT_SEED: u32 = 0;
+
+thread_local! {
+    static THREAD_RNG: RefCell<SmallRng> = RefCell::new(SmallRng::seed_from_u64(DEFAULT_SEED as u64));
+}
+
+/// Handle to the thread-local, seedable random number generator.
+///
+/// Unlike `rand::rng()`, the generator is deterministic: every thread starts from `DEFAULT_SEED`
+/// and the renderer reseeds it with `reseed` before every sample, so the sequence of random numbers
+/// depends only on the seed and on the sample being rendered, not on Rayon's scheduling.
+#[derive(Clone, Copy)]
+pub struct SeededRng;
+
+/// Returns handle to the thread-local generator, drop-in replacement for `rand::rng()`.
+pub fn rng() -> SeededRng {
+    SeededRng
+}
+
+/// Restarts thread-local generator from `seed`.
+pub fn reseed(seed: u64) {
+    THREAD_RNG.with_borrow_mut(|rng| *rng = SmallRng::seed_from_u64(seed));
+}
+
+/// State of the thread-local generator, see `save`.
+#[derive(Clone)]
+pub struct RngState(SmallRng);
+
+/// Returns current state of the thread-local generator, so that a sample can be interrupted
+/// and continued later with `restore`.
+pub fn save() -> RngState {
+    THREAD_RNG.with_borrow(|rng| RngState(rng.clone()))
+}
+
+/// Continues thread-local generator from the `state` returned by `save`.
+pub fn restore(state: RngState) {
+    THREAD_RNG.with_borrow_mut(|rng| *rng = state.0);
+}
+
+/// Derives independent seed of the stream used for `sample`-th sample of the `pixel`-th pixel.
+pub fn sample_seed(seed: u64, pixel: u64, sample: u64) -> u64 {
+    const PIXEL_MULTIPLIER: u64 = 0x9e37_79b9_7f4a_7c15;
+    const SAMPLE_MULTIPLIER: u64 = 0xc2b2_ae3d_27d4_eb4f;
+    let pixel_seed = mix(seed ^ pixel.wrapping_mul(PIXEL_MULTIPLIER));
+    mix(pixel_seed ^ sample.wrapping_mul(SAMPLE_MULTIPLIER))
+}
+
+/// Combines `values` into a single well-mixed hash.
+pub fn hash(values: &[u64]) -> u64 {
+    const INITIAL_HASH: u64 = 0x243f_6a88_85a3_08d3;
+    values
+        .iter()
+        .fold(INITIAL_HASH, |hash, value| mix(hash ^ mix(*value)))
+}
+
+/// Converts random bits into `f64` uniformly distributed in `[0, 1)` range.
+pub fn unit_f64(bits: u64) -> f64 {
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Finalizer of the SplitMix64 generator, scrambles bits of `value`.
+fn mix(value: u64) -> u64 {
+    let mut z = value;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+impl RngCore for SeededRng {
+    fn next_u32(&mut self) -> u32 {
+        THREAD_RNG.with_borrow_mut(|rng| rng.next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        THREAD_RNG.with_borrow_mut(|rng| rng.next_u64())
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        THREAD_RNG.with_borrow_mut(|rng| rng.fill_bytes(dst))
+    }
+}